@@ -0,0 +1,290 @@
+use clap::{Parser, ValueEnum};
+use mr_house::commands::slot_machine::{
+    default_jackpot_pools, gore_slots_definition, load_jackpot_pools, load_machine_definition, SpinKind,
+};
+use mr_house::Error;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Plays a slot machine offline and reports how it actually paid out. Needs no Discord token and
+/// never touches the bank.
+#[derive(Parser)]
+#[command(name = "mr_house-sim")]
+struct Args {
+    /// Machine definition (.toml or .json). Defaults to the built-in gore machine.
+    #[arg(long)]
+    machine: Option<PathBuf>,
+
+    /// Progressive jackpot pools the machine can feed. Defaults to the built-in pools. The
+    /// simulated machine is the only one feeding them.
+    #[arg(long)]
+    jackpot_pools: Option<PathBuf>,
+
+    /// Number of paid spins to play. Free spins they win are played on top.
+    #[arg(long, default_value_t = 100_000)]
+    spins: u64,
+
+    /// Seed for the RNG, so a run can be repeated exactly. Random when omitted.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Rolling jackpot to start from. The machine's minimum jackpot is used when lower.
+    #[arg(long, default_value_t = 0.0)]
+    starting_jackpot: f64,
+
+    /// Record the rolling jackpot every this many spins. Defaults to 100 samples over the run.
+    #[arg(long)]
+    sample_every: Option<u64>,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Serialize)]
+struct SimulationReport {
+    machine: String,
+    seed: u64,
+    spins: u64,
+    free_spins: u64,
+    bonus_rounds: u64,
+    total_wagered: f64,
+    total_paid: f64,
+    rtp: f64,
+    expected_rtp: f64,
+    hit_frequency: f64,
+    jackpot_hits: u64,
+    average_jackpot: f64,
+    progressive_hits: BTreeMap<String, u64>,
+    progressive_paid: f64,
+    /// Largest peak-to-trough drop in the player's running net result.
+    max_drawdown: f64,
+    payout_histogram: BTreeMap<u64, u64>,
+    jackpot_growth: Vec<JackpotSample>,
+}
+
+#[derive(Serialize)]
+struct JackpotSample {
+    spin: u64,
+    jackpot: f64,
+}
+
+fn main() -> Result<(), Error> {
+    let args = Args::parse();
+
+    let definition = match &args.machine {
+        Some(path) => load_machine_definition(path)?,
+        None => gore_slots_definition(),
+    };
+    let pools = match &args.jackpot_pools {
+        Some(path) => load_jackpot_pools(path)?,
+        None => default_jackpot_pools()?,
+    };
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut slot_machine = definition
+        .build(args.starting_jackpot, &pools)?
+        .with_rng(ChaCha8Rng::seed_from_u64(seed));
+    let sample_every = args.sample_every.unwrap_or((args.spins / 100).max(1));
+    let cost = slot_machine.cost_per_play as f64;
+
+    let mut total_paid = 0.0;
+    let mut hits = 0u64;
+    let mut jackpot_hits = 0u64;
+    let mut jackpot_winnings = 0.0;
+    let mut net = 0.0;
+    let mut peak_net = 0.0;
+    let mut max_drawdown = 0.0;
+    let mut free_spins = 0u64;
+    let mut bonus_rounds = 0u64;
+    let mut payout_histogram: BTreeMap<u64, u64> = BTreeMap::new();
+    let progressive_jackpots = slot_machine.progressive_jackpots().to_vec();
+    let mut pool_values: Vec<f64> = progressive_jackpots.iter().map(|progressive| progressive.pool.seed as f64).collect();
+    let mut progressive_hits: BTreeMap<String, u64> = progressive_jackpots
+        .iter()
+        .map(|progressive| (progressive.pool.name.clone(), 0))
+        .collect();
+    let mut progressive_paid = 0.0;
+    let mut jackpot_growth = vec![JackpotSample {
+        spin: 0,
+        jackpot: slot_machine.rolling_jackpot(),
+    }];
+
+    for spin in 1..=args.spins {
+        net -= cost;
+        // A paid spin and every free spin it leads to are played back to back.
+        let mut pending_free_spins = 0u64;
+        let mut kind = SpinKind::Paid;
+        loop {
+            let play_result = slot_machine.play(kind);
+            if kind == SpinKind::Paid {
+                for (value, progressive) in pool_values.iter_mut().zip(&progressive_jackpots) {
+                    *value += cost * progressive.contribution_rate;
+                }
+            }
+            let mut progressive_payout = 0u64;
+            for &index in &play_result.progressive_hits {
+                let progressive = &progressive_jackpots[index];
+                let paid = pool_values[index].floor();
+                progressive_payout += paid as u64;
+                pool_values[index] = progressive.pool.seed as f64 + (pool_values[index] - paid);
+                *progressive_hits.entry(progressive.pool.name.clone()).or_insert(0) += 1;
+            }
+            progressive_paid += progressive_payout as f64;
+            let payout = (play_result.payout as u64 + progressive_payout) as f64;
+
+            total_paid += payout;
+            net += payout;
+            if payout > 0.0 {
+                hits += 1;
+            }
+            if play_result.is_jackpot {
+                jackpot_hits += 1;
+                jackpot_winnings += payout;
+            }
+            if play_result.free_spins_awarded > 0 {
+                bonus_rounds += 1;
+                pending_free_spins += play_result.free_spins_awarded as u64;
+            }
+            *payout_histogram.entry(payout as u64).or_insert(0) += 1;
+
+            if pending_free_spins == 0 {
+                break;
+            }
+            pending_free_spins -= 1;
+            free_spins += 1;
+            kind = SpinKind::Free;
+        }
+
+        peak_net = f64::max(peak_net, net);
+        max_drawdown = f64::max(max_drawdown, peak_net - net);
+
+        if spin % sample_every == 0 {
+            jackpot_growth.push(JackpotSample {
+                spin,
+                jackpot: slot_machine.rolling_jackpot(),
+            });
+        }
+    }
+
+    let total_wagered = cost * args.spins as f64;
+    let report = SimulationReport {
+        machine: slot_machine.name().to_string(),
+        seed,
+        spins: args.spins,
+        free_spins,
+        bonus_rounds,
+        total_wagered,
+        total_paid,
+        rtp: ratio(total_paid, total_wagered),
+        expected_rtp: slot_machine.analysis().rtp,
+        hit_frequency: ratio(hits as f64, (args.spins + free_spins) as f64),
+        jackpot_hits,
+        average_jackpot: ratio(jackpot_winnings, jackpot_hits as f64),
+        progressive_hits,
+        progressive_paid,
+        max_drawdown,
+        payout_histogram,
+        jackpot_growth,
+    };
+
+    match args.format {
+        OutputFormat::Text => print_text(&report),
+        OutputFormat::Csv => print_csv(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    Ok(())
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+fn print_text(report: &SimulationReport) {
+    println!("Machine: {} (seed {})", report.machine, report.seed);
+    println!("Spins: {}", report.spins);
+    if report.bonus_rounds > 0 {
+        println!("Bonus Rounds: {} ({} free spins)", report.bonus_rounds, report.free_spins);
+    }
+    println!("Total Wagered: {:.2}", report.total_wagered);
+    println!("Total Paid: {:.2}", report.total_paid);
+    println!("RTP: {:.2}% (expected {:.2}%)", report.rtp * 100.0, report.expected_rtp * 100.0);
+    println!("Hit Frequency: {:.2}%", report.hit_frequency * 100.0);
+    println!("Jackpot Hits: {}", report.jackpot_hits);
+    if report.jackpot_hits > 0 {
+        println!("Jackpot Frequency: 1 in {:.0}", report.spins as f64 / report.jackpot_hits as f64);
+        println!("Average Jackpot: {:.2}", report.average_jackpot);
+    }
+    for (pool, hits) in &report.progressive_hits {
+        println!("{} Pool Hits: {}", pool, hits);
+    }
+    if !report.progressive_hits.is_empty() {
+        println!("Progressive Paid: {:.2}", report.progressive_paid);
+    }
+    println!("Max Drawdown: {:.2}", report.max_drawdown);
+
+    println!();
+    println!("Payout Histogram:");
+    for (payout, count) in &report.payout_histogram {
+        println!(
+            "  {:>8}: {:>10} ({:.4}%)",
+            payout,
+            count,
+            ratio(*count as f64, (report.spins + report.free_spins) as f64) * 100.0
+        );
+    }
+
+    println!();
+    println!("Jackpot Growth:");
+    for sample in &report.jackpot_growth {
+        println!("  spin {:>10}: {:.2}", sample.spin, sample.jackpot);
+    }
+}
+
+/// Three tables separated by blank lines, each with its own header row, so they paste straight
+/// into a spreadsheet.
+fn print_csv(report: &SimulationReport) {
+    println!("metric,value");
+    println!("machine,{}", report.machine);
+    println!("seed,{}", report.seed);
+    println!("spins,{}", report.spins);
+    println!("free_spins,{}", report.free_spins);
+    println!("bonus_rounds,{}", report.bonus_rounds);
+    println!("total_wagered,{}", report.total_wagered);
+    println!("total_paid,{}", report.total_paid);
+    println!("rtp,{}", report.rtp);
+    println!("expected_rtp,{}", report.expected_rtp);
+    println!("hit_frequency,{}", report.hit_frequency);
+    println!("jackpot_hits,{}", report.jackpot_hits);
+    println!("average_jackpot,{}", report.average_jackpot);
+    for (pool, hits) in &report.progressive_hits {
+        println!("{}_pool_hits,{}", pool, hits);
+    }
+    println!("progressive_paid,{}", report.progressive_paid);
+    println!("max_drawdown,{}", report.max_drawdown);
+
+    println!();
+    println!("payout,count");
+    for (payout, count) in &report.payout_histogram {
+        println!("{},{}", payout, count);
+    }
+
+    println!();
+    println!("spin,jackpot");
+    for sample in &report.jackpot_growth {
+        println!("{},{}", sample.spin, sample.jackpot);
+    }
+}
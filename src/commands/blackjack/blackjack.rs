@@ -0,0 +1,380 @@
+use crate::commands::cards::{Card, Rank, Shoe};
+use crate::Error;
+
+/// Most hands a player can end up with by splitting.
+pub const MAX_HANDS: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlackjackRules {
+    pub decks: u32,
+    /// Whether the dealer draws to a soft 17 rather than standing on it.
+    pub dealer_hits_soft_17: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Hit,
+    Stand,
+    Double,
+    Split,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// The dealer shows an ace and the player hasn't said whether they want insurance.
+    Insurance,
+    PlayerTurn,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Blackjack,
+    Win,
+    Push,
+    Lose,
+}
+
+impl Outcome {
+    /// What a hand returns per Libcoin staked on it, the stake included.
+    pub fn multiplier(self) -> f64 {
+        match self {
+            Outcome::Blackjack => 2.5,
+            Outcome::Win => 2.0,
+            Outcome::Push => 1.0,
+            Outcome::Lose => 0.0,
+        }
+    }
+}
+
+/// Best total for `cards`, and whether it's soft, i.e. an ace is still counting as 11.
+pub fn hand_total(cards: &[Card]) -> (u32, bool) {
+    let mut total = 0;
+    let mut soft_aces = 0;
+    for card in cards {
+        total += match card.rank {
+            Rank::Ace => {
+                soft_aces += 1;
+                11
+            }
+            Rank::Jack | Rank::Queen | Rank::King => 10,
+            rank => rank.value() as u32,
+        };
+    }
+    while total > 21 && soft_aces > 0 {
+        total -= 10;
+        soft_aces -= 1;
+    }
+
+    (total, soft_aces > 0)
+}
+
+fn is_natural(cards: &[Card]) -> bool {
+    cards.len() == 2 && hand_total(cards).0 == 21
+}
+
+pub struct PlayerHand {
+    pub cards: Vec<Card>,
+    pub doubled: bool,
+    /// Split hands can't make blackjack; 21 on them is just 21.
+    pub from_split: bool,
+    done: bool,
+}
+
+impl PlayerHand {
+    fn new(cards: Vec<Card>, from_split: bool) -> Self {
+        PlayerHand {
+            cards,
+            doubled: false,
+            from_split,
+            done: false,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        hand_total(&self.cards).0
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.total() > 21
+    }
+
+    pub fn is_blackjack(&self) -> bool {
+        !self.from_split && is_natural(&self.cards)
+    }
+}
+
+/// One round of blackjack against the dealer, dealt from the player's shoe. The round only knows
+/// about cards; stakes are the caller's to track, hand by hand.
+pub struct BlackjackRound {
+    rules: BlackjackRules,
+    shoe: Shoe,
+    dealer: Vec<Card>,
+    hands: Vec<PlayerHand>,
+    active_hand: usize,
+    phase: Phase,
+    insured: bool,
+}
+
+impl BlackjackRound {
+    pub fn deal(rules: BlackjackRules, mut shoe: Shoe) -> Self {
+        let (first, hole, second, up) = (shoe.draw(), shoe.draw(), shoe.draw(), shoe.draw());
+        let mut round = BlackjackRound {
+            rules,
+            shoe,
+            dealer: vec![up, hole],
+            hands: vec![PlayerHand::new(vec![first, second], false)],
+            active_hand: 0,
+            phase: Phase::Insurance,
+            insured: false,
+        };
+        if up.rank != Rank::Ace {
+            round.start_player_turn();
+        }
+
+        round
+    }
+
+    /// Hands the shoe back once the round is done with it, for the next round to carry on from.
+    pub fn into_shoe(self) -> Shoe {
+        self.shoe
+    }
+
+    pub fn rules(&self) -> BlackjackRules {
+        self.rules
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn hands(&self) -> &[PlayerHand] {
+        &self.hands
+    }
+
+    /// The hand being played, while it's the player's turn.
+    pub fn active_hand(&self) -> Option<usize> {
+        (self.phase == Phase::PlayerTurn).then_some(self.active_hand)
+    }
+
+    /// The dealer's cards as far as the player can see them: only the up card until the round is over.
+    pub fn dealer_cards(&self) -> &[Card] {
+        match self.phase {
+            Phase::Finished => &self.dealer,
+            _ => &self.dealer[..1],
+        }
+    }
+
+    pub fn dealer_total(&self) -> u32 {
+        hand_total(self.dealer_cards()).0
+    }
+
+    pub fn dealer_has_blackjack(&self) -> bool {
+        self.phase == Phase::Finished && is_natural(&self.dealer)
+    }
+
+    pub fn insured(&self) -> bool {
+        self.insured
+    }
+
+    /// Insurance pays 2:1 when the dealer turns out to have blackjack.
+    pub fn insurance_pays(&self) -> bool {
+        self.insured && self.dealer_has_blackjack()
+    }
+
+    pub fn decide_insurance(&mut self, insure: bool) -> Result<(), Error> {
+        if self.phase != Phase::Insurance {
+            return Err(Error::from("Insurance is only offered before anyone plays."));
+        }
+        self.insured = insure;
+        self.start_player_turn();
+
+        Ok(())
+    }
+
+    pub fn can(&self, action: Action) -> bool {
+        let Some(hand) = self.active_hand().map(|index| &self.hands[index]) else {
+            return false;
+        };
+        match action {
+            Action::Hit | Action::Stand => true,
+            Action::Double => hand.cards.len() == 2,
+            Action::Split => {
+                hand.cards.len() == 2 && hand.cards[0].rank == hand.cards[1].rank && self.hands.len() < MAX_HANDS
+            }
+        }
+    }
+
+    pub fn play(&mut self, action: Action) -> Result<(), Error> {
+        if !self.can(action) {
+            return Err(Error::from("You can't do that with this hand."));
+        }
+
+        let index = self.active_hand;
+        match action {
+            Action::Hit => {
+                let card = self.shoe.draw();
+                let hand = &mut self.hands[index];
+                hand.cards.push(card);
+                hand.done = hand.total() >= 21;
+            }
+            Action::Stand => self.hands[index].done = true,
+            Action::Double => {
+                let card = self.shoe.draw();
+                let hand = &mut self.hands[index];
+                hand.cards.push(card);
+                hand.doubled = true;
+                hand.done = true;
+            }
+            Action::Split => {
+                let moved = self.hands[index].cards.pop().expect("a hand being split has two cards");
+                self.hands[index].from_split = true;
+                self.hands.insert(index + 1, PlayerHand::new(vec![moved], true));
+                for split_index in [index, index + 1] {
+                    let card = self.shoe.draw();
+                    let hand = &mut self.hands[split_index];
+                    hand.cards.push(card);
+                    // Split aces get one card each and no say in the matter.
+                    hand.done = moved.rank == Rank::Ace || hand.total() >= 21;
+                }
+            }
+        }
+        self.advance();
+
+        Ok(())
+    }
+
+    pub fn outcome(&self, hand: &PlayerHand) -> Outcome {
+        let dealer_total = hand_total(&self.dealer).0;
+        let dealer_blackjack = is_natural(&self.dealer);
+        if hand.is_bust() {
+            Outcome::Lose
+        } else if hand.is_blackjack() {
+            if dealer_blackjack {
+                Outcome::Push
+            } else {
+                Outcome::Blackjack
+            }
+        } else if dealer_blackjack {
+            Outcome::Lose
+        } else if dealer_total > 21 || hand.total() > dealer_total {
+            Outcome::Win
+        } else if hand.total() == dealer_total {
+            Outcome::Push
+        } else {
+            Outcome::Lose
+        }
+    }
+
+    /// The dealer peeks for blackjack, and a natural on either side ends the round on the spot.
+    fn start_player_turn(&mut self) {
+        self.phase = Phase::PlayerTurn;
+        if is_natural(&self.dealer) || self.hands[0].is_blackjack() {
+            self.hands[0].done = true;
+            self.phase = Phase::Finished;
+            return;
+        }
+        self.advance();
+    }
+
+    fn advance(&mut self) {
+        while self.active_hand < self.hands.len() && self.hands[self.active_hand].done {
+            self.active_hand += 1;
+        }
+        if self.active_hand == self.hands.len() {
+            self.play_dealer();
+            self.phase = Phase::Finished;
+        }
+    }
+
+    fn play_dealer(&mut self) {
+        // Nothing left to beat.
+        if self.hands.iter().all(PlayerHand::is_bust) {
+            return;
+        }
+        loop {
+            let (total, soft) = hand_total(&self.dealer);
+            if total < 17 || (total == 17 && soft && self.rules.dealer_hits_soft_17) {
+                self.dealer.push(self.shoe.draw());
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::cards::Suit;
+
+    fn cards(ranks: &[Rank]) -> Vec<Card> {
+        ranks.iter().map(|&rank| Card { rank, suit: Suit::Spades }).collect()
+    }
+
+    /// A finished round with `dealer` and `player` already dealt, for checking what it pays.
+    fn finished(dealer: &[Rank], player: &[Rank], from_split: bool) -> BlackjackRound {
+        BlackjackRound {
+            rules: BlackjackRules {
+                decks: 1,
+                dealer_hits_soft_17: false,
+            },
+            shoe: Shoe::new(1),
+            dealer: cards(dealer),
+            hands: vec![PlayerHand::new(cards(player), from_split)],
+            active_hand: 0,
+            phase: Phase::Finished,
+            insured: false,
+        }
+    }
+
+    fn outcome(dealer: &[Rank], player: &[Rank]) -> Outcome {
+        let round = finished(dealer, player, false);
+        round.outcome(&round.hands()[0])
+    }
+
+    #[test]
+    fn aces_count_eleven_until_that_would_bust() {
+        assert_eq!(hand_total(&cards(&[Rank::Ace, Rank::King])), (21, true));
+        assert_eq!(hand_total(&cards(&[Rank::Ace, Rank::Six])), (17, true));
+        assert_eq!(hand_total(&cards(&[Rank::Ace, Rank::Six, Rank::King])), (17, false));
+        assert_eq!(hand_total(&cards(&[Rank::Ace, Rank::Ace, Rank::Nine])), (21, true));
+        assert_eq!(hand_total(&cards(&[Rank::Ace, Rank::Ace, Rank::Ace, Rank::Ace])), (14, true));
+        assert_eq!(hand_total(&cards(&[Rank::King, Rank::Queen, Rank::Five])), (25, false));
+    }
+
+    #[test]
+    fn naturals_pay_three_to_two_unless_the_dealer_has_one_too() {
+        assert_eq!(outcome(&[Rank::King, Rank::Queen], &[Rank::Ace, Rank::Jack]), Outcome::Blackjack);
+        assert_eq!(outcome(&[Rank::Ace, Rank::Ten], &[Rank::Ace, Rank::Jack]), Outcome::Push);
+        assert_eq!(outcome(&[Rank::Ace, Rank::Ten], &[Rank::King, Rank::Queen]), Outcome::Lose);
+        // Three cards to 21 still loses to the dealer's natural.
+        assert_eq!(outcome(&[Rank::Ace, Rank::Ten], &[Rank::Seven, Rank::Seven, Rank::Seven]), Outcome::Lose);
+    }
+
+    #[test]
+    fn twenty_one_on_a_split_hand_is_not_blackjack() {
+        let round = finished(&[Rank::King, Rank::Queen], &[Rank::Ace, Rank::Jack], true);
+        assert_eq!(round.outcome(&round.hands()[0]), Outcome::Win);
+    }
+
+    #[test]
+    fn higher_total_wins_and_busting_always_loses() {
+        assert_eq!(outcome(&[Rank::King, Rank::Eight], &[Rank::King, Rank::Nine]), Outcome::Win);
+        assert_eq!(outcome(&[Rank::King, Rank::Nine], &[Rank::King, Rank::Eight]), Outcome::Lose);
+        assert_eq!(outcome(&[Rank::King, Rank::Eight], &[Rank::Nine, Rank::Nine]), Outcome::Push);
+        assert_eq!(outcome(&[Rank::King, Rank::Six, Rank::Nine], &[Rank::Ten, Rank::Eight]), Outcome::Win);
+        assert_eq!(
+            outcome(&[Rank::King, Rank::Six, Rank::Nine], &[Rank::Ten, Rank::Five, Rank::Nine]),
+            Outcome::Lose
+        );
+    }
+
+    #[test]
+    fn multipliers_include_the_stake() {
+        assert_eq!(Outcome::Blackjack.multiplier(), 2.5);
+        assert_eq!(Outcome::Win.multiplier(), 2.0);
+        assert_eq!(Outcome::Push.multiplier(), 1.0);
+        assert_eq!(Outcome::Lose.multiplier(), 0.0);
+    }
+}
@@ -0,0 +1,5 @@
+#[allow(clippy::module_inception)]
+pub mod blackjack;
+pub mod table;
+
+pub use blackjack::*;
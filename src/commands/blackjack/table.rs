@@ -0,0 +1,325 @@
+use super::{Action, BlackjackRound, BlackjackRules, Outcome, Phase, MAX_HANDS};
+use crate::commands::buttons::wait_for_press;
+use crate::commands::cards::format_cards;
+use crate::services::settlement::{call_off, describe_payout, place_wager, settle, stake_wager, PayoutStatus, WagerId};
+use crate::{Context, Error, BLACKJACK_DEALER_HITS_SOFT_17, BLACKJACK_DECKS, MR_HOUSE_ID};
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use serenity::builder::{CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter};
+use std::time::Duration;
+use tracing::error;
+
+const BLACKJACK_GAME: &str = "blackjack";
+const DEDUCT_MESSAGE: &str = "Playing blackjack";
+const GRANT_MESSAGE: &str = "Winning at blackjack";
+// How long the player gets for each decision before Mr. House plays it safe for them.
+const DECISION_TIMEOUT: Duration = Duration::from_secs(120);
+// Every hand split out and doubled, and every one of them won.
+const MAX_PAYOUT_MULTIPLIER: f64 = (MAX_HANDS * 4) as f64;
+
+const HIT: &str = "hit";
+const STAND: &str = "stand";
+const DOUBLE: &str = "double";
+const SPLIT: &str = "split";
+const INSURE: &str = "insure";
+const DECLINE_INSURANCE: &str = "no-insurance";
+
+/// The stakes riding on a round. Every extra stake (a double, a split, insurance) is a wager of its
+/// own, kept next to the hand it belongs to so each is settled on that hand's outcome.
+struct Stakes {
+    hands: Vec<Vec<(WagerId, f64)>>,
+    insurance: Option<(WagerId, f64)>,
+}
+
+impl Stakes {
+    fn on_hand(&self, index: usize) -> f64 {
+        self.hands[index].iter().map(|(_, stake)| stake).sum()
+    }
+}
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Play a hand of blackjack against Mr. House."),
+    description_localized("fr", "Jouez une main de blackjack contre Mr. House."),
+    description_localized("es-ES", "Juega una mano de blackjack contra Mr. House.")
+)]
+pub async fn blackjack(
+    ctx: Context<'_>,
+    #[description = "How much Libcoin to bet"]
+    #[description_localized("fr", "Combien de Libcoin miser")]
+    #[description_localized("es-ES", "Cuántas Libcoin apostar")]
+    #[min = 1]
+    bet: u32,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+    let bank = &ctx.data().bank;
+    let bet = bet as f64;
+
+    let wager_id = stake_wager(
+        bank.as_ref(),
+        user_id,
+        BLACKJACK_GAME,
+        bet,
+        bet * MAX_PAYOUT_MULTIPLIER,
+        DEDUCT_MESSAGE,
+        MR_HOUSE_ID,
+    )
+    .await?;
+    let mut stakes = Stakes {
+        hands: vec![vec![(wager_id, bet)]],
+        insurance: None,
+    };
+    let rules = BlackjackRules {
+        decks: *BLACKJACK_DECKS,
+        dealer_hits_soft_17: *BLACKJACK_DEALER_HITS_SOFT_17,
+    };
+    let mut round = BlackjackRound::deal(rules, ctx.data().blackjack_shoes.take(user_id, rules.decks));
+
+    let id_prefix = format!("{}-blackjack-", ctx.id());
+    let mut notice: Option<String> = None;
+    let mut reply = None;
+    while round.phase() != Phase::Finished {
+        // Only offered what can be paid for; a failed balance check just means no extras this turn.
+        let balance = bank.get_libcoin_balance(user_id).await.unwrap_or_else(|reason| {
+            error!("Couldn't check {user_id}'s balance mid-hand: {reason:?}");
+            0.0
+        });
+        let (buttons, custom_ids) = decision_buttons(&round, &id_prefix, balance, bet);
+        let update = CreateReply {
+            embeds: vec![round_embed(&round, &stakes, notice.take().as_deref(), None)],
+            components: Some(vec![CreateActionRow::Buttons(buttons)]),
+            ..Default::default()
+        };
+        match &reply {
+            None => match ctx.send(update).await {
+                Ok(handle) => reply = Some(handle),
+                Err(reason) => {
+                    call_off(bank.as_ref(), wager_id).await;
+                    return Err(reason.into());
+                }
+            },
+            Some(handle) => {
+                if let Err(reason) = handle.edit(ctx, update).await {
+                    error!("Couldn't update the blackjack table: {reason:?}");
+                }
+            }
+        }
+
+        let pressed = wait_for_press(ctx, &custom_ids, DECISION_TIMEOUT).await;
+        let choice = pressed.as_deref().and_then(|custom_id| custom_id.strip_prefix(&id_prefix));
+        match (round.phase(), choice) {
+            (Phase::Insurance, Some(INSURE)) => {
+                let insurance = bet / 2.0;
+                match place_wager(bank.as_ref(), user_id, BLACKJACK_GAME, insurance, DEDUCT_MESSAGE, MR_HOUSE_ID).await {
+                    Ok(insurance_wager) => {
+                        stakes.insurance = Some((insurance_wager, insurance));
+                        round.decide_insurance(true)?;
+                    }
+                    Err(_) => {
+                        notice = Some("I couldn't take the insurance money, so you're playing without it.".to_string());
+                        round.decide_insurance(false)?;
+                    }
+                }
+            }
+            (Phase::Insurance, _) => round.decide_insurance(false)?,
+            (Phase::PlayerTurn, Some(choice)) => {
+                let action = match choice {
+                    HIT => Action::Hit,
+                    DOUBLE => Action::Double,
+                    SPLIT => Action::Split,
+                    _ => Action::Stand,
+                };
+                if !round.can(action) {
+                    continue;
+                }
+                let hand_index = round.active_hand().unwrap_or_default();
+                if matches!(action, Action::Double | Action::Split) {
+                    let message = match action {
+                        Action::Double => "I couldn't take the extra bet, so you haven't doubled.",
+                        _ => "I couldn't take the extra bet, so you haven't split.",
+                    };
+                    match place_wager(bank.as_ref(), user_id, BLACKJACK_GAME, bet, DEDUCT_MESSAGE, MR_HOUSE_ID).await {
+                        Ok(extra_wager) if action == Action::Double => stakes.hands[hand_index].push((extra_wager, bet)),
+                        Ok(extra_wager) => stakes.hands.insert(hand_index + 1, vec![(extra_wager, bet)]),
+                        Err(_) => {
+                            notice = Some(message.to_string());
+                            continue;
+                        }
+                    }
+                }
+                round.play(action)?;
+            }
+            (Phase::PlayerTurn, None) => {
+                while round.phase() == Phase::PlayerTurn {
+                    round.play(Action::Stand)?;
+                }
+                notice = Some("You took too long, so Mr. House stood for you.".to_string());
+            }
+            (Phase::Finished, _) => {}
+        }
+    }
+
+    let mut payout = 0.0;
+    let mut payout_status = PayoutStatus::Delivered;
+    let mut settlements: Vec<(WagerId, f64, f64)> = Vec::new();
+    for (hand, wagers) in round.hands().iter().zip(&stakes.hands) {
+        let multiplier = round.outcome(hand).multiplier();
+        settlements.extend(wagers.iter().map(|(wager_id, stake)| (*wager_id, *stake, stake * multiplier)));
+    }
+    if let Some((insurance_wager, insurance)) = stakes.insurance {
+        let multiplier = if round.insurance_pays() { 3.0 } else { 0.0 };
+        settlements.push((insurance_wager, insurance, insurance * multiplier));
+    }
+    // Every wager gets settled even if one before it couldn't be; those are refunded afterwards.
+    let mut unsettled: Vec<(WagerId, f64)> = Vec::new();
+    for (wager_id, stake, wager_payout) in settlements {
+        match settle(bank.as_ref(), wager_id, user_id, wager_payout, GRANT_MESSAGE).await {
+            Ok(status) => {
+                if status == PayoutStatus::Queued {
+                    payout_status = PayoutStatus::Queued;
+                }
+                payout += wager_payout;
+            }
+            Err(_) => unsettled.push((wager_id, stake)),
+        }
+    }
+    if !unsettled.is_empty() {
+        for (wager_id, _) in &unsettled {
+            call_off(bank.as_ref(), *wager_id).await;
+        }
+        // Counted in the payout so the net shows the refund as coming back.
+        let refunded: f64 = unsettled.iter().map(|(_, stake)| stake).sum();
+        payout += refunded;
+        let apology = format!(
+            "Sorry, I couldn't settle {} of your wagers, so the {} staked on them is on its way back.",
+            unsettled.len(),
+            refunded
+        );
+        notice = Some(match notice {
+            Some(notice) => format!("{}\n{}", notice, apology),
+            None => apology,
+        });
+    }
+
+    let result = CreateReply {
+        embeds: vec![round_embed(&round, &stakes, notice.as_deref(), Some((payout, payout_status)))],
+        components: Some(Vec::new()),
+        ..Default::default()
+    };
+    ctx.data().blackjack_shoes.put_back(user_id, round.into_shoe());
+    match reply {
+        Some(handle) => handle.edit(ctx, result).await?,
+        None => {
+            ctx.send(result).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The buttons for the decision at hand, along with the custom ids of those that can be pressed.
+fn decision_buttons(round: &BlackjackRound, id_prefix: &str, balance: f64, bet: f64) -> (Vec<CreateButton>, Vec<String>) {
+    let choices: Vec<(&str, String, serenity::ButtonStyle, bool)> = match round.phase() {
+        Phase::Insurance => vec![
+            (INSURE, format!("Insurance ({})", bet / 2.0), serenity::ButtonStyle::Primary, balance >= bet / 2.0),
+            (DECLINE_INSURANCE, "No insurance".to_string(), serenity::ButtonStyle::Secondary, true),
+        ],
+        Phase::PlayerTurn => vec![
+            (HIT, "Hit".to_string(), serenity::ButtonStyle::Primary, true),
+            (STAND, "Stand".to_string(), serenity::ButtonStyle::Secondary, true),
+            (DOUBLE, "Double".to_string(), serenity::ButtonStyle::Success, round.can(Action::Double) && balance >= bet),
+            (SPLIT, "Split".to_string(), serenity::ButtonStyle::Success, round.can(Action::Split) && balance >= bet),
+        ],
+        Phase::Finished => Vec::new(),
+    };
+
+    let mut buttons = Vec::with_capacity(choices.len());
+    let mut custom_ids = Vec::with_capacity(choices.len());
+    for (choice, label, style, enabled) in choices {
+        let custom_id = format!("{}{}", id_prefix, choice);
+        buttons.push(CreateButton::new(&custom_id).label(label).style(style).disabled(!enabled));
+        if enabled {
+            custom_ids.push(custom_id);
+        }
+    }
+
+    (buttons, custom_ids)
+}
+
+fn round_embed(round: &BlackjackRound, stakes: &Stakes, notice: Option<&str>, settled: Option<(f64, PayoutStatus)>) -> CreateEmbed {
+    let dealer_cards = match round.phase() {
+        Phase::Finished => format_cards(round.dealer_cards()),
+        _ => format!("{} `??`", format_cards(round.dealer_cards())),
+    };
+    let dealer_total = if round.dealer_has_blackjack() {
+        "Blackjack".to_string()
+    } else {
+        round.dealer_total().to_string()
+    };
+
+    let mut embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title("🃏 Blackjack")
+        .field("Mr. House", format!("{}\n**{}**", dealer_cards, dealer_total), false);
+
+    for (index, hand) in round.hands().iter().enumerate() {
+        let name = match (round.hands().len(), round.active_hand() == Some(index)) {
+            (1, _) => "Your Hand".to_string(),
+            (_, true) => format!("▶ Hand {}", index + 1),
+            (_, false) => format!("Hand {}", index + 1),
+        };
+        let total = if hand.is_blackjack() {
+            "Blackjack".to_string()
+        } else if hand.is_bust() {
+            format!("{} (bust)", hand.total())
+        } else {
+            hand.total().to_string()
+        };
+        let mut value = format!("{}\n**{}** · Bet {}", format_cards(&hand.cards), total, stakes.on_hand(index));
+        if settled.is_some() {
+            let outcome = match round.outcome(hand) {
+                Outcome::Blackjack => "Blackjack! Pays 3:2",
+                Outcome::Win => "Win",
+                Outcome::Push => "Push",
+                Outcome::Lose => "Lose",
+            };
+            value.push_str(&format!("\n{}", outcome));
+        }
+        embed = embed.field(name, value, true);
+    }
+
+    if let Some((_, insurance)) = stakes.insurance {
+        let status = match round.phase() {
+            Phase::Finished if round.insurance_pays() => "pays 2:1",
+            Phase::Finished => "lost",
+            _ => "taken",
+        };
+        embed = embed.field("Insurance", format!("{} ({})", insurance, status), true);
+    }
+
+    let description = match (round.phase(), settled) {
+        (Phase::Insurance, _) => "Mr. House shows an ace. Insurance?".to_string(),
+        (Phase::PlayerTurn, _) => "Hit, stand, double or split?".to_string(),
+        (Phase::Finished, Some((payout, _))) => {
+            let staked: f64 = (0..round.hands().len()).map(|index| stakes.on_hand(index)).sum::<f64>()
+                + stakes.insurance.map_or(0.0, |(_, insurance)| insurance);
+            format!("Net **{:+}**", payout - staked)
+        }
+        (Phase::Finished, None) => String::new(),
+    };
+    let description = match notice {
+        Some(notice) => format!("{}\n{}", notice, description),
+        None => description,
+    };
+
+    if let Some((payout, status)) = settled {
+        embed = embed.field("Payout", describe_payout(payout, status), true);
+    }
+
+    let rules = round.rules();
+    let soft_17 = if rules.dealer_hits_soft_17 { "Dealer hits soft 17" } else { "Dealer stands on soft 17" };
+    embed.description(description).footer(CreateEmbedFooter::new(format!(
+        "{} decks · {} · Blackjack pays 3:2 · Insurance pays 2:1",
+        rules.decks, soft_17
+    )))
+}
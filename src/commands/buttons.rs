@@ -0,0 +1,40 @@
+use crate::Context;
+use poise::serenity_prelude as serenity;
+use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use std::time::Duration;
+use tracing::error;
+
+/// How long a command can keep offering buttons. Discord interactions expire after 15 minutes and
+/// replies stop working with them, so the buttons go a little before then.
+pub const BUTTON_WINDOW: Duration = Duration::from_secs(14 * 60);
+
+/// Waits up to `timeout` for the player who ran the command to press one of the buttons in
+/// `custom_ids`, returning which one they pressed, already acknowledged. Anyone else pressing them
+/// is told the buttons aren't theirs.
+pub async fn wait_for_press(ctx: Context<'_>, custom_ids: &[String], timeout: Duration) -> Option<String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let interaction = serenity::ComponentInteractionCollector::new(ctx)
+            .channel_id(ctx.channel_id())
+            .custom_ids(custom_ids.to_vec())
+            .timeout(deadline.saturating_duration_since(tokio::time::Instant::now()))
+            .await?;
+
+        if interaction.user.id == ctx.author().id {
+            if let Err(reason) = interaction.create_response(ctx, CreateInteractionResponse::Acknowledge).await {
+                error!("Couldn't acknowledge a button press: {reason:?}");
+            }
+            return Some(interaction.data.custom_id);
+        }
+
+        let response = CreateInteractionResponseMessage::new()
+            .content(format!("Only {} can use these buttons.", ctx.author().name))
+            .ephemeral(true);
+        if let Err(reason) = interaction
+            .create_response(ctx, CreateInteractionResponse::Message(response))
+            .await
+        {
+            error!("Couldn't turn away someone else's button press: {reason:?}");
+        }
+    }
+}
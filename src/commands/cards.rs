@@ -0,0 +1,210 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+const DECK_SIZE: usize = 52;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suit {
+    Spades,
+    Hearts,
+    Diamonds,
+    Clubs,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Suit::Spades => "♠",
+            Suit::Hearts => "♥",
+            Suit::Diamonds => "♦",
+            Suit::Clubs => "♣",
+        }
+    }
+}
+
+/// Ranks in ascending order, aces high.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Two = 2,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
+    /// 2 through 14, aces high.
+    pub fn value(self) -> u8 {
+        self as u8
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+            Rank::Ace => "A",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Card {
+    pub rank: Rank,
+    pub suit: Suit,
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.rank.label(), self.suit.symbol())
+    }
+}
+
+/// Joins cards up for an embed, e.g. "`A♠` `10♥`".
+pub fn format_cards(cards: &[Card]) -> String {
+    cards.iter().map(|card| format!("`{}`", card)).collect::<Vec<_>>().join(" ")
+}
+
+/// `decks` shuffled 52-card decks dealt from the top. A shoe that runs dry is reshuffled from
+/// fresh decks rather than ever running out mid-hand.
+#[derive(Debug)]
+pub struct Shoe {
+    decks: u32,
+    cards: Vec<Card>,
+    rng: StdRng,
+}
+
+impl Shoe {
+    pub fn new(decks: u32) -> Self {
+        let mut shoe = Shoe {
+            decks: decks.max(1),
+            cards: Vec::new(),
+            rng: StdRng::from_os_rng(),
+        };
+        shoe.shuffle();
+        shoe
+    }
+
+    pub fn decks(&self) -> u32 {
+        self.decks
+    }
+
+    /// Whether the cut card, three quarters of the way into the shoe, has come out. Tables shuffle
+    /// up between hands once it has instead of dealing the shoe down to its last cards.
+    pub fn past_cut_card(&self) -> bool {
+        self.cards.len() < self.decks as usize * DECK_SIZE / 4
+    }
+
+    pub fn draw(&mut self) -> Card {
+        if self.cards.is_empty() {
+            self.shuffle();
+        }
+        self.cards.pop().expect("a freshly shuffled shoe has cards")
+    }
+
+    /// Gathers every card back in and shuffles them.
+    pub fn shuffle(&mut self) {
+        self.cards = (0..self.decks)
+            .flat_map(|_| Suit::ALL.into_iter().flat_map(|suit| Rank::ALL.into_iter().map(move |rank| Card { rank, suit })))
+            .collect();
+        self.cards.shuffle(&mut self.rng);
+    }
+}
+
+/// Shoes kept between hands, one per player, so each hand is dealt from where their last one left
+/// off and the shoe is only shuffled once its cut card comes out.
+#[derive(Default)]
+pub struct ShoeRack {
+    shoes: Mutex<HashMap<u64, Shoe>>,
+}
+
+impl ShoeRack {
+    /// Takes `user_id`'s shoe for a hand, shuffled up if its cut card is out. A player without one,
+    /// or whose shoe holds a different number of decks, gets a fresh shoe.
+    pub fn take(&self, user_id: u64, decks: u32) -> Shoe {
+        match self.shoes.lock().unwrap().remove(&user_id) {
+            Some(mut shoe) if shoe.decks() == decks.max(1) => {
+                if shoe.past_cut_card() {
+                    shoe.shuffle();
+                }
+                shoe
+            }
+            _ => Shoe::new(decks),
+        }
+    }
+
+    /// Puts a shoe back once its hand is over.
+    pub fn put_back(&self, user_id: u64, shoe: Shoe) {
+        self.shoes.lock().unwrap().insert(user_id, shoe);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn racked_shoes_carry_on_until_the_cut_card() {
+        let rack = ShoeRack::default();
+        let mut shoe = rack.take(1, 1);
+        for _ in 0..39 {
+            shoe.draw();
+        }
+        assert!(!shoe.past_cut_card());
+        rack.put_back(1, shoe);
+
+        let mut shoe = rack.take(1, 1);
+        assert_eq!(shoe.cards.len(), 13);
+        shoe.draw();
+        assert!(shoe.past_cut_card());
+        rack.put_back(1, shoe);
+
+        assert_eq!(rack.take(1, 1).cards.len(), DECK_SIZE);
+    }
+
+    #[test]
+    fn a_shoe_of_the_wrong_size_is_swapped_for_a_fresh_one() {
+        let rack = ShoeRack::default();
+        rack.put_back(1, Shoe::new(1));
+        assert_eq!(rack.take(1, 6).cards.len(), 6 * DECK_SIZE);
+    }
+}
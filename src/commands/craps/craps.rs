@@ -0,0 +1,478 @@
+use crate::services::settlement::WagerId;
+use crate::Error;
+use rand::Rng;
+
+pub const POINT_NUMBERS: [u8; 6] = [4, 5, 6, 8, 9, 10];
+/// Free odds can be taken or laid up to this many times the bet they back.
+pub const MAX_ODDS_MULTIPLE: f64 = 3.0;
+
+const DIE_FACES: [&str; 6] = ["⚀", "⚁", "⚂", "⚃", "⚄", "⚅"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BetKind {
+    PassLine,
+    DontPass,
+    Come,
+    DontCome,
+    Place,
+    /// Free odds behind one of the other line bets, paid at true odds.
+    Odds,
+}
+
+impl BetKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BetKind::PassLine => "pass",
+            BetKind::DontPass => "dont_pass",
+            BetKind::Come => "come",
+            BetKind::DontCome => "dont_come",
+            BetKind::Place => "place",
+            BetKind::Odds => "odds",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<BetKind> {
+        match kind {
+            "pass" => Some(BetKind::PassLine),
+            "dont_pass" => Some(BetKind::DontPass),
+            "come" => Some(BetKind::Come),
+            "dont_come" => Some(BetKind::DontCome),
+            "place" => Some(BetKind::Place),
+            "odds" => Some(BetKind::Odds),
+            _ => None,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            BetKind::PassLine => "Pass Line",
+            BetKind::DontPass => "Don't Pass",
+            BetKind::Come => "Come",
+            BetKind::DontCome => "Don't Come",
+            BetKind::Place => "Place",
+            BetKind::Odds => "Odds",
+        }
+    }
+
+    /// The most a bet of this kind can return per Libcoin on it, the stake included.
+    pub fn max_return(self) -> f64 {
+        match self {
+            BetKind::PassLine | BetKind::DontPass | BetKind::Come | BetKind::DontCome => 2.0,
+            BetKind::Place => 2.8,
+            BetKind::Odds => 3.0,
+        }
+    }
+
+    /// Bets against the shooter, which win on a 7 once there's a point.
+    fn is_dont(self) -> bool {
+        matches!(self, BetKind::DontPass | BetKind::DontCome)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CrapsBet {
+    pub id: i64,
+    pub kind: BetKind,
+    /// A come or don't come bet's own point once it has moved there, a place bet's number, or the
+    /// point odds are working on.
+    pub point: Option<u8>,
+    pub amount: f64,
+    /// The line bet odds are backing.
+    pub backs: Option<i64>,
+    pub wager_id: WagerId,
+}
+
+/// A bet that's allowed on the table as it stands, ready to be staked.
+#[derive(Debug, Clone)]
+pub struct NewBet {
+    pub kind: BetKind,
+    pub point: Option<u8>,
+    pub amount: f64,
+    pub backs: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// Pays this much back, the stake included.
+    Won(f64),
+    Lost,
+    /// The stake comes back untouched.
+    Returned,
+    /// A come or don't come bet moves to its own point.
+    Travelled(u8),
+}
+
+/// A player's own table: where the puck is and what they have riding on it. `version` goes up with
+/// every change, so two commands can't both act on the same layout.
+#[derive(Debug, Clone)]
+pub struct CrapsTable {
+    pub point: Option<u8>,
+    pub version: i64,
+    pub bets: Vec<CrapsBet>,
+}
+
+pub fn roll_dice(rng: &mut impl Rng) -> (u8, u8) {
+    (rng.random_range(1..=6), rng.random_range(1..=6))
+}
+
+/// "⚂ ⚃ (7)".
+pub fn format_dice((first, second): (u8, u8)) -> String {
+    format!(
+        "{} {} ({})",
+        DIE_FACES[first as usize - 1],
+        DIE_FACES[second as usize - 1],
+        first + second
+    )
+}
+
+/// What odds on `point` pay per Libcoin, as (won, staked): true odds, or their inverse when laid.
+fn true_odds(point: u8, lay: bool) -> (f64, f64) {
+    let odds = match point {
+        4 | 10 => (2.0, 1.0),
+        5 | 9 => (3.0, 2.0),
+        _ => (6.0, 5.0),
+    };
+    if lay {
+        (odds.1, odds.0)
+    } else {
+        odds
+    }
+}
+
+fn place_odds(number: u8) -> (f64, f64) {
+    match number {
+        4 | 10 => (9.0, 5.0),
+        5 | 9 => (7.0, 5.0),
+        _ => (7.0, 6.0),
+    }
+}
+
+impl CrapsTable {
+    pub fn bet(&self, id: i64) -> Option<&CrapsBet> {
+        self.bets.iter().find(|bet| bet.id == id)
+    }
+
+    /// How the bet would be described at the table, e.g. "Come on 6" or "Odds behind Don't Pass".
+    pub fn describe(&self, bet: &CrapsBet) -> String {
+        match (bet.kind, bet.point) {
+            (BetKind::Odds, _) => match bet.backs.and_then(|backs| self.bet(backs)) {
+                Some(backing) => format!("Odds behind {}", self.describe(backing)),
+                None => "Odds".to_string(),
+            },
+            (BetKind::Come | BetKind::DontCome | BetKind::Place, Some(point)) => {
+                format!("{} on {}", bet.kind.title(), point)
+            }
+            (kind, _) => kind.title().to_string(),
+        }
+    }
+
+    /// Checks a bet can go down right now, and works out which point it plays on. Odds go behind the
+    /// pass or don't pass line when `number` is left out, otherwise behind the come or don't come
+    /// bet sitting on `number`.
+    pub fn check_new_bet(&self, kind: BetKind, amount: f64, number: Option<u8>, lay: bool) -> Result<NewBet, Error> {
+        let new_bet = |point: Option<u8>, backs: Option<i64>| NewBet { kind, point, amount, backs };
+        match kind {
+            BetKind::PassLine | BetKind::DontPass => match self.point {
+                None => Ok(new_bet(None, None)),
+                Some(_) => Err(Error::from(
+                    "Line bets go down before the come-out roll. With a point on, bet come or don't come instead.",
+                )),
+            },
+            BetKind::Come | BetKind::DontCome => match self.point {
+                Some(_) => Ok(new_bet(None, None)),
+                None => Err(Error::from(
+                    "Come bets are for once there's a point. On the come-out roll, bet the pass line instead.",
+                )),
+            },
+            BetKind::Place => match number {
+                Some(number) if POINT_NUMBERS.contains(&number) => Ok(new_bet(Some(number), None)),
+                _ => Err(Error::from("Place bets go on 4, 5, 6, 8, 9 or 10.")),
+            },
+            BetKind::Odds => {
+                let backing = match number {
+                    None => {
+                        let line = if lay { BetKind::DontPass } else { BetKind::PassLine };
+                        self.point.and_then(|_| self.bets.iter().find(|bet| bet.kind == line))
+                    }
+                    Some(number) => {
+                        let come = if lay { BetKind::DontCome } else { BetKind::Come };
+                        self.bets.iter().find(|bet| bet.kind == come && bet.point == Some(number))
+                    }
+                };
+                let Some(backing) = backing else {
+                    return Err(Error::from(
+                        "There's nothing for those odds to go behind. Odds need a line or come bet that's on a point.",
+                    ));
+                };
+
+                let already_behind: f64 = self
+                    .bets
+                    .iter()
+                    .filter(|bet| bet.backs == Some(backing.id))
+                    .map(|bet| bet.amount)
+                    .sum();
+                let limit = backing.amount * MAX_ODDS_MULTIPLE - already_behind;
+                if amount > limit {
+                    return Err(Error::from(format!(
+                        "Odds are capped at {}x the bet they back; you can put up to {} more behind {}.",
+                        MAX_ODDS_MULTIPLE,
+                        limit.max(0.0),
+                        self.describe(backing)
+                    )));
+                }
+                let point = match backing.kind {
+                    BetKind::PassLine | BetKind::DontPass => self.point,
+                    _ => backing.point,
+                };
+                Ok(new_bet(point, Some(backing.id)))
+            }
+        }
+    }
+
+    /// What a roll totalling `total` does to each bet it has any effect on.
+    pub fn resolve(&self, total: u8) -> Vec<(&CrapsBet, Resolution)> {
+        let mut resolutions: Vec<(&CrapsBet, Resolution)> = self
+            .bets
+            .iter()
+            .filter(|bet| bet.kind != BetKind::Odds)
+            .filter_map(|bet| self.resolve_bet(bet, total).map(|resolution| (bet, resolution)))
+            .collect();
+
+        // Odds ride on whatever happens to the bet they back.
+        let odds: Vec<(&CrapsBet, Resolution)> = self
+            .bets
+            .iter()
+            .filter(|bet| bet.kind == BetKind::Odds)
+            .filter_map(|odds| {
+                let backing = odds.backs.and_then(|backs| self.bet(backs))?;
+                let (_, backing_resolution) = resolutions.iter().find(|(bet, _)| bet.id == backing.id)?;
+                // Come bet odds are off on the come-out roll.
+                if backing.kind == BetKind::Come && self.point.is_none() {
+                    return Some((odds, Resolution::Returned));
+                }
+                let resolution = match backing_resolution {
+                    Resolution::Won(_) => {
+                        let (won, staked) = true_odds(odds.point?, backing.kind.is_dont());
+                        Resolution::Won(odds.amount + odds.amount * won / staked)
+                    }
+                    other => *other,
+                };
+                Some((odds, resolution))
+            })
+            .collect();
+        resolutions.extend(odds);
+
+        resolutions
+    }
+
+    /// Where the puck sits after a roll totalling `total`.
+    pub fn point_after(&self, total: u8) -> Option<u8> {
+        match self.point {
+            None if POINT_NUMBERS.contains(&total) => Some(total),
+            Some(point) if total == point || total == 7 => None,
+            point => point,
+        }
+    }
+
+    fn resolve_bet(&self, bet: &CrapsBet, total: u8) -> Option<Resolution> {
+        let even_money = Resolution::Won(bet.amount * 2.0);
+        match bet.kind {
+            BetKind::PassLine | BetKind::Come => {
+                let point = if bet.kind == BetKind::PassLine { self.point } else { bet.point };
+                match (point, total) {
+                    (None, 7 | 11) => Some(even_money),
+                    (None, 2 | 3 | 12) => Some(Resolution::Lost),
+                    (None, _) if bet.kind == BetKind::Come => Some(Resolution::Travelled(total)),
+                    (None, _) => None,
+                    (Some(_), 7) => Some(Resolution::Lost),
+                    (Some(point), total) if total == point => Some(even_money),
+                    (Some(_), _) => None,
+                }
+            }
+            BetKind::DontPass | BetKind::DontCome => {
+                let point = if bet.kind == BetKind::DontPass { self.point } else { bet.point };
+                match (point, total) {
+                    (None, 2 | 3) => Some(even_money),
+                    (None, 12) => Some(Resolution::Returned),
+                    (None, 7 | 11) => Some(Resolution::Lost),
+                    (None, _) if bet.kind == BetKind::DontCome => Some(Resolution::Travelled(total)),
+                    (None, _) => None,
+                    (Some(_), 7) => Some(even_money),
+                    (Some(point), total) if total == point => Some(Resolution::Lost),
+                    (Some(_), _) => None,
+                }
+            }
+            // Place bets are off on the come-out roll, and come down with their winnings when they hit.
+            BetKind::Place => match (self.point, bet.point) {
+                (None, _) | (_, None) => None,
+                (Some(_), _) if total == 7 => Some(Resolution::Lost),
+                (Some(_), Some(number)) if total == number => {
+                    let (won, staked) = place_odds(number);
+                    Some(Resolution::Won(bet.amount + bet.amount * won / staked))
+                }
+                _ => None,
+            },
+            BetKind::Odds => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bet(id: i64, kind: BetKind, point: Option<u8>, amount: f64, backs: Option<i64>) -> CrapsBet {
+        CrapsBet {
+            id,
+            kind,
+            point,
+            amount,
+            backs,
+            wager_id: id,
+        }
+    }
+
+    fn table(point: Option<u8>, bets: Vec<CrapsBet>) -> CrapsTable {
+        CrapsTable { point, version: 0, bets }
+    }
+
+    /// Each bet's resolution on `total`, by bet id.
+    fn resolve(table: &CrapsTable, total: u8) -> Vec<(i64, Resolution)> {
+        table.resolve(total).into_iter().map(|(bet, resolution)| (bet.id, resolution)).collect()
+    }
+
+    #[test]
+    fn the_come_out_roll_settles_the_line_bets() {
+        let come_out = table(
+            None,
+            vec![bet(1, BetKind::PassLine, None, 10.0, None), bet(2, BetKind::DontPass, None, 10.0, None)],
+        );
+        assert_eq!(resolve(&come_out, 7), vec![(1, Resolution::Won(20.0)), (2, Resolution::Lost)]);
+        assert_eq!(resolve(&come_out, 11), vec![(1, Resolution::Won(20.0)), (2, Resolution::Lost)]);
+        assert_eq!(resolve(&come_out, 2), vec![(1, Resolution::Lost), (2, Resolution::Won(20.0))]);
+        assert_eq!(resolve(&come_out, 3), vec![(1, Resolution::Lost), (2, Resolution::Won(20.0))]);
+        // Bar 12: craps for the pass line, a push for don't pass.
+        assert_eq!(resolve(&come_out, 12), vec![(1, Resolution::Lost), (2, Resolution::Returned)]);
+        assert_eq!(resolve(&come_out, 6), vec![]);
+        assert_eq!(come_out.point_after(6), Some(6));
+        assert_eq!(come_out.point_after(7), None);
+    }
+
+    #[test]
+    fn with_a_point_on_the_line_bets_wait_for_it_or_a_seven() {
+        let point_on = table(
+            Some(6),
+            vec![bet(1, BetKind::PassLine, None, 10.0, None), bet(2, BetKind::DontPass, None, 10.0, None)],
+        );
+        assert_eq!(resolve(&point_on, 6), vec![(1, Resolution::Won(20.0)), (2, Resolution::Lost)]);
+        assert_eq!(resolve(&point_on, 7), vec![(1, Resolution::Lost), (2, Resolution::Won(20.0))]);
+        assert_eq!(resolve(&point_on, 12), vec![]);
+        assert_eq!(resolve(&point_on, 11), vec![]);
+        assert_eq!(point_on.point_after(6), None);
+        assert_eq!(point_on.point_after(8), Some(6));
+    }
+
+    #[test]
+    fn come_bets_travel_to_their_own_point() {
+        let point_on = table(
+            Some(4),
+            vec![bet(1, BetKind::Come, None, 10.0, None), bet(2, BetKind::DontCome, None, 10.0, None)],
+        );
+        assert_eq!(resolve(&point_on, 9), vec![(1, Resolution::Travelled(9)), (2, Resolution::Travelled(9))]);
+        assert_eq!(resolve(&point_on, 11), vec![(1, Resolution::Won(20.0)), (2, Resolution::Lost)]);
+        assert_eq!(resolve(&point_on, 12), vec![(1, Resolution::Lost), (2, Resolution::Returned)]);
+
+        let travelled = table(
+            Some(4),
+            vec![bet(1, BetKind::Come, Some(9), 10.0, None), bet(2, BetKind::DontCome, Some(9), 10.0, None)],
+        );
+        assert_eq!(resolve(&travelled, 9), vec![(1, Resolution::Won(20.0)), (2, Resolution::Lost)]);
+        assert_eq!(resolve(&travelled, 4), vec![]);
+    }
+
+    #[test]
+    fn odds_pay_true_odds_taken_or_laid() {
+        let taken = |point: u8| {
+            let table = table(
+                Some(point),
+                vec![bet(1, BetKind::PassLine, None, 10.0, None), bet(2, BetKind::Odds, Some(point), 30.0, Some(1))],
+            );
+            resolve(&table, point)[1]
+        };
+        assert_eq!(taken(4), (2, Resolution::Won(90.0)));
+        assert_eq!(taken(5), (2, Resolution::Won(75.0)));
+        assert_eq!(taken(6), (2, Resolution::Won(66.0)));
+
+        let laid = table(
+            Some(4),
+            vec![bet(1, BetKind::DontPass, None, 10.0, None), bet(2, BetKind::Odds, Some(4), 30.0, Some(1))],
+        );
+        assert_eq!(resolve(&laid, 7), vec![(1, Resolution::Won(20.0)), (2, Resolution::Won(45.0))]);
+        assert_eq!(resolve(&laid, 4), vec![(1, Resolution::Lost), (2, Resolution::Lost)]);
+    }
+
+    #[test]
+    fn come_odds_are_off_on_the_come_out_roll() {
+        let come_out = table(
+            None,
+            vec![bet(1, BetKind::Come, Some(6), 10.0, None), bet(2, BetKind::Odds, Some(6), 20.0, Some(1))],
+        );
+        assert_eq!(resolve(&come_out, 7), vec![(1, Resolution::Lost), (2, Resolution::Returned)]);
+        assert_eq!(resolve(&come_out, 6), vec![(1, Resolution::Won(20.0)), (2, Resolution::Returned)]);
+
+        let point_on = table(Some(4), come_out.bets.clone());
+        assert_eq!(resolve(&point_on, 6), vec![(1, Resolution::Won(20.0)), (2, Resolution::Won(44.0))]);
+    }
+
+    #[test]
+    fn place_bets_work_only_once_there_is_a_point() {
+        let bets = vec![
+            bet(1, BetKind::Place, Some(4), 5.0, None),
+            bet(2, BetKind::Place, Some(5), 5.0, None),
+            bet(3, BetKind::Place, Some(6), 6.0, None),
+        ];
+        assert_eq!(resolve(&table(None, bets.clone()), 6), vec![]);
+        assert_eq!(resolve(&table(None, bets.clone()), 7), vec![]);
+
+        let point_on = table(Some(8), bets);
+        assert_eq!(resolve(&point_on, 4), vec![(1, Resolution::Won(14.0))]);
+        assert_eq!(resolve(&point_on, 5), vec![(2, Resolution::Won(12.0))]);
+        assert_eq!(resolve(&point_on, 6), vec![(3, Resolution::Won(13.0))]);
+        assert_eq!(resolve(&point_on, 7).len(), 3);
+        assert!(resolve(&point_on, 7).iter().all(|(_, resolution)| *resolution == Resolution::Lost));
+    }
+
+    #[test]
+    fn new_bets_have_to_suit_the_puck() {
+        let come_out = table(None, vec![bet(1, BetKind::PassLine, None, 10.0, None)]);
+        assert!(come_out.check_new_bet(BetKind::PassLine, 10.0, None, false).is_ok());
+        assert!(come_out.check_new_bet(BetKind::Come, 10.0, None, false).is_err());
+        // No point yet, so nothing for odds to go behind.
+        assert!(come_out.check_new_bet(BetKind::Odds, 10.0, None, false).is_err());
+        assert!(come_out.check_new_bet(BetKind::Place, 10.0, Some(7), false).is_err());
+        assert_eq!(come_out.check_new_bet(BetKind::Place, 10.0, Some(8), false).unwrap().point, Some(8));
+
+        let point_on = table(
+            Some(5),
+            vec![bet(1, BetKind::PassLine, None, 10.0, None), bet(2, BetKind::Come, Some(9), 10.0, None)],
+        );
+        assert!(point_on.check_new_bet(BetKind::PassLine, 10.0, None, false).is_err());
+        assert!(point_on.check_new_bet(BetKind::DontCome, 10.0, None, false).is_ok());
+        let odds = point_on.check_new_bet(BetKind::Odds, 30.0, None, false).unwrap();
+        assert_eq!((odds.point, odds.backs), (Some(5), Some(1)));
+        let come_odds = point_on.check_new_bet(BetKind::Odds, 30.0, Some(9), false).unwrap();
+        assert_eq!((come_odds.point, come_odds.backs), (Some(9), Some(2)));
+        assert!(point_on.check_new_bet(BetKind::Odds, 10.0, None, true).is_err());
+        assert!(point_on.check_new_bet(BetKind::Odds, 10.0, Some(6), false).is_err());
+    }
+
+    #[test]
+    fn odds_are_capped_at_a_multiple_of_the_bet_they_back() {
+        let point_on = table(
+            Some(5),
+            vec![bet(1, BetKind::PassLine, None, 10.0, None), bet(2, BetKind::Odds, Some(5), 20.0, Some(1))],
+        );
+        assert!(point_on.check_new_bet(BetKind::Odds, 10.0, None, false).is_ok());
+        assert!(point_on.check_new_bet(BetKind::Odds, 11.0, None, false).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+#[allow(clippy::module_inception)]
+pub mod craps;
+pub mod table;
+
+pub use craps::*;
@@ -0,0 +1,316 @@
+use super::{format_dice, roll_dice, BetKind, CrapsBet, CrapsTable, Resolution, MAX_ODDS_MULTIPLE};
+use crate::commands::buttons::{wait_for_press, BUTTON_WINDOW};
+use crate::services::craps::{add_craps_bet, craps_table, record_craps_roll, CrapsBetChange};
+use crate::services::settlement::{call_off, deliver_payouts, describe_payout, stake_wager, PayoutStatus};
+use crate::{Context, Error, MR_HOUSE_ID};
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use serenity::builder::{CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+const CRAPS_GAME: &str = "craps";
+const DEDUCT_MESSAGE: &str = "Playing craps";
+const GRANT_MESSAGE: &str = "Winning at craps";
+const ROLL_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum CrapsBetChoice {
+    #[name = "Pass Line"]
+    PassLine,
+    #[name = "Don't Pass"]
+    DontPass,
+    #[name = "Come"]
+    Come,
+    #[name = "Don't Come"]
+    DontCome,
+    #[name = "Take Odds"]
+    TakeOdds,
+    #[name = "Lay Odds"]
+    LayOdds,
+    #[name = "Place"]
+    Place,
+}
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "See your craps table and roll the dice."),
+    description_localized("fr", "Consultez votre table de craps et lancez les dés."),
+    description_localized("es-ES", "Mira tu mesa de craps y tira los dados.")
+)]
+pub async fn craps(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+    let started_at = Instant::now();
+    let roll_id = format!("{}-craps-roll", ctx.id());
+
+    let table = load_table(user_id)?;
+    let reply = ctx
+        .send(CreateReply {
+            embeds: vec![table_embed(&table, None)],
+            components: Some(roll_button(&table, &roll_id)),
+            ..Default::default()
+        })
+        .await?;
+    if table.bets.is_empty() {
+        return Ok(());
+    }
+
+    while started_at.elapsed() < BUTTON_WINDOW
+        && wait_for_press(ctx, std::slice::from_ref(&roll_id), ROLL_TIMEOUT).await.is_some()
+    {
+        let (table, roll) = roll(ctx, user_id).await?;
+        reply
+            .edit(
+                ctx,
+                CreateReply {
+                    embeds: vec![table_embed(&table, Some(&roll))],
+                    components: Some(roll_button(&table, &roll_id)),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        if table.bets.is_empty() {
+            return Ok(());
+        }
+    }
+
+    let table = load_table(user_id)?;
+    reply
+        .edit(
+            ctx,
+            CreateReply {
+                embeds: vec![table_embed(&table, None)],
+                components: Some(Vec::new()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Put a bet on your craps table. It stays there until a roll settles it."),
+    description_localized("fr", "Placez une mise sur votre table de craps. Elle y reste jusqu'à ce qu'un lancer la règle."),
+    description_localized("es-ES", "Pon una apuesta en tu mesa de craps. Se queda ahí hasta que una tirada la resuelva.")
+)]
+pub async fn crapsbet(
+    ctx: Context<'_>,
+    #[description = "Which bet to make"]
+    #[description_localized("fr", "Quelle mise faire")]
+    #[description_localized("es-ES", "Qué apuesta hacer")]
+    bet: CrapsBetChoice,
+    #[description = "How much Libcoin to bet"]
+    #[description_localized("fr", "Combien de Libcoin miser")]
+    #[description_localized("es-ES", "Cuántas Libcoin apostar")]
+    #[min = 1]
+    amount: u32,
+    #[description = "The number to place, or the come point to put odds behind"]
+    #[description_localized("fr", "Le nombre à placer, ou le point du come derrière lequel miser la cote")]
+    #[description_localized("es-ES", "El número a apostar, o el punto del come al que añadir odds")]
+    #[min = 4]
+    #[max = 10]
+    number: Option<u8>,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+    let bank = &ctx.data().bank;
+    let amount = amount as f64;
+    let table = load_table(user_id)?;
+
+    let (kind, lay) = match bet {
+        CrapsBetChoice::PassLine => (BetKind::PassLine, false),
+        CrapsBetChoice::DontPass => (BetKind::DontPass, false),
+        CrapsBetChoice::Come => (BetKind::Come, false),
+        CrapsBetChoice::DontCome => (BetKind::DontCome, false),
+        CrapsBetChoice::TakeOdds => (BetKind::Odds, false),
+        CrapsBetChoice::LayOdds => (BetKind::Odds, true),
+        CrapsBetChoice::Place => (BetKind::Place, false),
+    };
+    let new_bet = table.check_new_bet(kind, amount, number, lay)?;
+
+    let exposure: f64 = table.bets.iter().map(|bet| bet.amount * bet.kind.max_return()).sum::<f64>()
+        + amount * kind.max_return();
+    let wager_id = stake_wager(bank.as_ref(), user_id, CRAPS_GAME, amount, exposure, DEDUCT_MESSAGE, MR_HOUSE_ID).await?;
+    if let Err(reason) = add_craps_bet(
+        user_id,
+        table.version,
+        new_bet.kind.as_str(),
+        new_bet.point,
+        new_bet.amount,
+        new_bet.backs,
+        wager_id,
+    ) {
+        error!("Couldn't put craps bet for wager {wager_id} on the table: {reason:?}");
+        call_off(bank.as_ref(), wager_id).await;
+        return Err(Error::from(
+            "Sorry, I couldn't get that bet onto your table, so your stake is on its way back.",
+        ));
+    }
+
+    let table = load_table(user_id)?;
+    ctx.send(CreateReply {
+        embeds: vec![table_embed(&table, None).description("Bet placed. Roll with `/craps` whenever you're ready.")],
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// A roll and what it did, for showing on the table.
+struct Roll {
+    dice: (u8, u8),
+    results: Vec<String>,
+    payout: f64,
+    payout_status: PayoutStatus,
+}
+
+/// Rolls the dice on the player's table and settles every bet the roll decides.
+async fn roll(ctx: Context<'_>, user_id: u64) -> Result<(CrapsTable, Roll), Error> {
+    let table = load_table(user_id)?;
+    let dice = roll_dice(&mut rand::rng());
+    let total = dice.0 + dice.1;
+    let resolutions = table.resolve(total);
+
+    let mut results = Vec::new();
+    let mut payout = 0.0;
+    // Odds come off before the bets they back.
+    let mut changes: Vec<CrapsBetChange> = Vec::new();
+    for (bet, resolution) in resolutions.iter().rev() {
+        let description = table.describe(bet);
+        let change = match *resolution {
+            Resolution::Travelled(point) => {
+                results.push(format!("{} moves to {}", description, point));
+                CrapsBetChange::Travelled { bet_id: bet.id, point }
+            }
+            Resolution::Won(won) => {
+                results.push(format!("{} ({}) **wins {}**", description, format_amount(bet.amount), format_amount(won)));
+                settled(bet, won)
+            }
+            Resolution::Returned => {
+                results.push(format!("{} ({}) is returned", description, format_amount(bet.amount)));
+                settled(bet, bet.amount)
+            }
+            Resolution::Lost => {
+                results.push(format!("{} ({}) loses", description, format_amount(bet.amount)));
+                settled(bet, 0.0)
+            }
+        };
+        if let CrapsBetChange::Settled { payout: bet_payout, .. } = change {
+            payout += bet_payout;
+        }
+        changes.push(change);
+    }
+    results.reverse();
+
+    let payout_entries = record_craps_roll(user_id, table.version, table.point_after(total), &changes, GRANT_MESSAGE)
+        .map_err(|reason| {
+            error!("Couldn't record craps roll for {user_id}: {reason:?}");
+            Error::from("Sorry, the dice rolled off the table. Nothing on it has changed, so give it another go.")
+        })?;
+    let payout_status = deliver_payouts(ctx.data().bank.as_ref(), &payout_entries).await;
+
+    let roll = Roll {
+        dice,
+        results,
+        payout,
+        payout_status,
+    };
+    Ok((load_table(user_id)?, roll))
+}
+
+fn settled(bet: &CrapsBet, payout: f64) -> CrapsBetChange {
+    CrapsBetChange::Settled {
+        bet_id: bet.id,
+        wager_id: bet.wager_id,
+        payout,
+    }
+}
+
+fn load_table(user_id: u64) -> Result<CrapsTable, Error> {
+    let row = craps_table(user_id).map_err(|_| Error::from("Sorry, I couldn't find your craps table."))?;
+    let bets = row
+        .bets
+        .into_iter()
+        .filter_map(|bet| match BetKind::parse(&bet.kind) {
+            Some(kind) => Some(CrapsBet {
+                id: bet.id,
+                kind,
+                point: bet.point,
+                amount: bet.amount,
+                backs: bet.backs,
+                wager_id: bet.wager_id,
+            }),
+            None => {
+                error!("Craps bet {} has unknown kind {:?}", bet.id, bet.kind);
+                None
+            }
+        })
+        .collect();
+
+    Ok(CrapsTable {
+        point: row.point,
+        version: row.version,
+        bets,
+    })
+}
+
+fn roll_button(table: &CrapsTable, roll_id: &str) -> Vec<CreateActionRow> {
+    if table.bets.is_empty() {
+        return Vec::new();
+    }
+    vec![CreateActionRow::Buttons(vec![CreateButton::new(roll_id)
+        .style(serenity::ButtonStyle::Primary)
+        .label("Roll")])]
+}
+
+fn table_embed(table: &CrapsTable, roll: Option<&Roll>) -> CreateEmbed {
+    let point = match table.point {
+        Some(point) => format!("**{}** (puck on)", point),
+        None => "Come-out roll (puck off)".to_string(),
+    };
+    let bets = if table.bets.is_empty() {
+        "Nothing on the table. Put a bet down with `/crapsbet`.".to_string()
+    } else {
+        table
+            .bets
+            .iter()
+            .map(|bet| format!("{}: {}", table.describe(bet), format_amount(bet.amount)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title("🎲 Craps")
+        .field("Point", point, false)
+        .field("Bets", bets, false)
+        .footer(CreateEmbedFooter::new(format!(
+            "Odds up to {}x, paid at true odds | Place pays 9:5 on 4/10, 7:5 on 5/9, 7:6 on 6/8",
+            MAX_ODDS_MULTIPLE
+        )));
+
+    match roll {
+        None => embed,
+        Some(roll) => {
+            let mut description = format!("🎲 {}", format_dice(roll.dice));
+            if roll.results.is_empty() {
+                description.push_str("\nNo action this roll.");
+            } else {
+                description.push_str(&format!("\n{}", roll.results.join("\n")));
+            }
+            let payout = describe_payout(format_amount(roll.payout), roll.payout_status);
+            embed.description(description).field("Payout", payout, true)
+        }
+    }
+}
+
+/// Whole amounts as they are, anything else to the hundredth.
+fn format_amount(amount: f64) -> String {
+    if amount.fract() == 0.0 {
+        format!("{:.0}", amount)
+    } else {
+        format!("{:.2}", amount)
+    }
+}
@@ -0,0 +1,286 @@
+use crate::commands::cards::{Card, Shoe};
+use crate::commands::craps::{format_dice, roll_dice};
+use crate::services::settlement::{call_off, describe_payout, settle, settle_wager, stake_wager, WagerId};
+use crate::{Context, Error, DUEL_RAKE_PERCENT, MR_HOUSE_ID};
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use rand::Rng;
+use serenity::Mentionable;
+use serenity::builder::{
+    CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+};
+use std::time::Duration;
+use tracing::error;
+
+const DUEL_GAME: &str = "duel";
+const DEDUCT_MESSAGE: &str = "Putting up a duel stake";
+const GRANT_MESSAGE: &str = "Winning a duel";
+const LOSS_MESSAGE: &str = "Losing a duel";
+// Left unanswered this long, the challenge is called off and the challenger's stake refunded.
+const ACCEPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum DuelGame {
+    #[name = "Coin flip"]
+    Coinflip,
+    #[name = "Dice"]
+    Dice,
+    #[name = "High card"]
+    HighCard,
+}
+
+/// How a duel played out: one line per throw, ties included, and who took it.
+struct Showdown {
+    throws: Vec<String>,
+    challenger_wins: bool,
+}
+
+impl DuelGame {
+    pub fn title(self) -> &'static str {
+        match self {
+            DuelGame::Coinflip => "Coin Flip",
+            DuelGame::Dice => "Dice",
+            DuelGame::HighCard => "High Card",
+        }
+    }
+
+    fn rules(self) -> &'static str {
+        match self {
+            DuelGame::Coinflip => "The challenger has heads, the opponent tails.",
+            DuelGame::Dice => "Both roll two dice and the higher total wins. Ties roll again.",
+            DuelGame::HighCard => "Both draw from one deck and the higher card wins, aces high. Ties draw again.",
+        }
+    }
+
+    /// Plays the duel out, going again on a tie until somebody wins.
+    fn play(self, challenger: &str, opponent: &str) -> Showdown {
+        let mut rng = rand::rng();
+        let mut throws = Vec::new();
+        let challenger_wins = match self {
+            DuelGame::Coinflip => {
+                let heads = rng.random_bool(0.5);
+                throws.push(format!("🪙 **{}**", if heads { "Heads" } else { "Tails" }));
+                heads
+            }
+            DuelGame::Dice => loop {
+                let (first, second) = (roll_dice(&mut rng), roll_dice(&mut rng));
+                throws.push(format!(
+                    "{}: 🎲 {} | {}: 🎲 {}",
+                    challenger,
+                    format_dice(first),
+                    opponent,
+                    format_dice(second)
+                ));
+                let (first, second) = (first.0 + first.1, second.0 + second.1);
+                if first != second {
+                    break first > second;
+                }
+            },
+            DuelGame::HighCard => {
+                let mut deck = Shoe::new(1);
+                loop {
+                    let (first, second): (Card, Card) = (deck.draw(), deck.draw());
+                    throws.push(format!("{}: `{}` | {}: `{}`", challenger, first, opponent, second));
+                    if first.rank != second.rank {
+                        break first.rank > second.rank;
+                    }
+                }
+            }
+        };
+
+        Showdown { throws, challenger_wins }
+    }
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Challenge another player to a duel, winner takes the pot."),
+    description_localized("fr", "Défiez un autre joueur en duel, le gagnant remporte la mise."),
+    description_localized("es-ES", "Reta a otro jugador a un duelo, el ganador se lleva el bote.")
+)]
+pub async fn duel(
+    ctx: Context<'_>,
+    #[description = "Who to challenge"]
+    #[description_localized("fr", "Qui défier")]
+    #[description_localized("es-ES", "A quién retar")]
+    opponent: serenity::User,
+    #[description = "How much Libcoin each of you puts up"]
+    #[description_localized("fr", "Combien de Libcoin chacun met en jeu")]
+    #[description_localized("es-ES", "Cuántas Libcoin pone cada uno")]
+    #[min = 1]
+    amount: u32,
+    #[description = "What to settle it with"]
+    #[description_localized("fr", "Comment départager le duel")]
+    #[description_localized("es-ES", "Cómo decidir el duelo")]
+    game: DuelGame,
+) -> Result<(), Error> {
+    let challenger = ctx.author().clone();
+    let bank = &ctx.data().bank;
+    let stake = amount as f64;
+
+    if opponent.id == challenger.id {
+        return Err(Error::from("You can't duel yourself."));
+    }
+    if opponent.bot {
+        return Err(Error::from("Bots don't duel. Pick someone with a pulse."));
+    }
+
+    // The challenger's stake is held by the house from the moment the challenge goes out. The prize
+    // comes out of both stakes, so the house has nothing of its own to cover.
+    let challenger_wager =
+        stake_wager(bank.as_ref(), challenger.id.get(), DUEL_GAME, stake, 0.0, DEDUCT_MESSAGE, MR_HOUSE_ID).await?;
+
+    let accept_id = format!("{}-duel-accept", ctx.id());
+    let decline_id = format!("{}-duel-decline", ctx.id());
+    let challenge = CreateReply {
+        content: Some(opponent.mention().to_string()),
+        embeds: vec![challenge_embed(&challenger, &opponent, amount, game)],
+        components: Some(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(&accept_id).label("Accept").style(serenity::ButtonStyle::Success),
+            CreateButton::new(&decline_id).label("Decline").style(serenity::ButtonStyle::Danger),
+        ])]),
+        ..Default::default()
+    };
+    let reply = match ctx.send(challenge).await {
+        Ok(reply) => reply,
+        Err(reason) => {
+            call_off(bank.as_ref(), challenger_wager).await;
+            return Err(reason.into());
+        }
+    };
+
+    let answer = wait_for_answer(ctx, &opponent, stake, &accept_id, &decline_id).await;
+    let Some(opponent_wager) = answer else {
+        call_off(bank.as_ref(), challenger_wager).await;
+        let embed = CreateEmbed::new()
+            .color(0x5b9e48)
+            .title(format!("⚔️ {} Duel", game.title()))
+            .description(format!(
+                "{} didn't take up {}'s challenge. The {} Libcoin stake has gone back.",
+                opponent.name, challenger.name, amount
+            ));
+        reply
+            .edit(
+                ctx,
+                CreateReply {
+                    embeds: vec![embed],
+                    components: Some(Vec::new()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let showdown = game.play(&challenger.name, &opponent.name);
+    let (winner, winner_wager, loser, loser_wager) = if showdown.challenger_wins {
+        (&challenger, challenger_wager, &opponent, opponent_wager)
+    } else {
+        (&opponent, opponent_wager, &challenger, challenger_wager)
+    };
+    let pot = stake * 2.0;
+    let rake = pot * *DUEL_RAKE_PERCENT / 100.0;
+    let prize = pot - rake;
+
+    if let Err(reason) = settle_wager(bank.as_ref(), loser_wager, loser.id.get(), 0.0, LOSS_MESSAGE).await {
+        error!("Couldn't settle losing duel wager {loser_wager}: {reason:?}");
+    }
+    let payout_status = settle(bank.as_ref(), winner_wager, winner.id.get(), prize, GRANT_MESSAGE).await?;
+
+    let prize = describe_payout(format!("{:.2}", prize), payout_status);
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(format!("⚔️ {} Duel", game.title()))
+        .description(format!("{}\n\n**{} wins!**", showdown.throws.join("\n"), winner.name))
+        .field("Pot", format!("{:.2}", pot), true)
+        .field("House rake", format!("{:.2}", rake), true)
+        .field("Prize", prize, true);
+    reply
+        .edit(
+            ctx,
+            CreateReply {
+                content: Some(format!("{} vs {}", challenger.mention(), opponent.mention())),
+                embeds: vec![embed],
+                components: Some(Vec::new()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Waits for the opponent to accept, staking their side when they do. Comes back empty if they
+/// decline, the challenger calls it off, or nobody answers in time.
+async fn wait_for_answer(
+    ctx: Context<'_>,
+    opponent: &serenity::User,
+    stake: f64,
+    accept_id: &str,
+    decline_id: &str,
+) -> Option<WagerId> {
+    let bank = &ctx.data().bank;
+    let deadline = tokio::time::Instant::now() + ACCEPT_TIMEOUT;
+    loop {
+        let interaction = serenity::ComponentInteractionCollector::new(ctx)
+            .channel_id(ctx.channel_id())
+            .custom_ids(vec![accept_id.to_string(), decline_id.to_string()])
+            .timeout(deadline.saturating_duration_since(tokio::time::Instant::now()))
+            .await?;
+
+        let pressed_by = interaction.user.id;
+        let declined = interaction.data.custom_id == decline_id;
+        // Either side can back out, but only the one challenged can accept.
+        if pressed_by != opponent.id && !(declined && pressed_by == ctx.author().id) {
+            let response = CreateInteractionResponseMessage::new()
+                .content(format!("This challenge is for {}.", opponent.name))
+                .ephemeral(true);
+            if let Err(reason) = interaction
+                .create_response(ctx, CreateInteractionResponse::Message(response))
+                .await
+            {
+                error!("Couldn't turn away someone else's duel button press: {reason:?}");
+            }
+            continue;
+        }
+
+        if let Err(reason) = interaction.create_response(ctx, CreateInteractionResponse::Acknowledge).await {
+            error!("Couldn't acknowledge a duel button press: {reason:?}");
+        }
+        if declined {
+            return None;
+        }
+
+        match stake_wager(bank.as_ref(), opponent.id.get(), DUEL_GAME, stake, 0.0, DEDUCT_MESSAGE, MR_HOUSE_ID).await {
+            Ok(wager_id) => return Some(wager_id),
+            Err(reason) => {
+                let followup = CreateInteractionResponseFollowup::new()
+                    .content(reason.to_string())
+                    .ephemeral(true);
+                if let Err(reason) = interaction.create_followup(ctx, followup).await {
+                    error!("Couldn't tell a duel opponent their stake didn't go through: {reason:?}");
+                }
+            }
+        }
+    }
+}
+
+fn challenge_embed(challenger: &serenity::User, opponent: &serenity::User, amount: u32, game: DuelGame) -> CreateEmbed {
+    CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(format!("⚔️ {} Duel", game.title()))
+        .description(format!(
+            "{} challenges {} for {} Libcoin each. {}",
+            challenger.name,
+            opponent.name,
+            amount,
+            game.rules()
+        ))
+        .footer(CreateEmbedFooter::new(format!(
+            "Stakes are held by the house until it's settled | House rake: {}% of the pot | Expires in {} seconds",
+            *DUEL_RAKE_PERCENT,
+            ACCEPT_TIMEOUT.as_secs()
+        )))
+}
@@ -0,0 +1,140 @@
+use crate::commands::slot_machine::SpinKind;
+use crate::services::fairness::{derive_spin_seed, get_commitment, rotate_server_seed, set_client_seed};
+use crate::services::spin_history::{format_seed, spins_for_server_seed};
+use crate::{Context, Error};
+use poise::CreateReply;
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+
+const MAX_CLIENT_SEED_LENGTH: usize = 64;
+const VERIFIED_SPINS_SHOWN: usize = 10;
+const DERIVATION: &str = "Spin seed = first 8 bytes of SHA-256(\"server seed:client seed:nonce\")\n\
+Reel stops = ChaCha8Rng::seed_from_u64(spin seed), then random_range(0..strip length) for each reel from left to right (rand 0.9)";
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "See the seeds your slot spins are drawn from, or pick your own client seed."),
+    description_localized("fr", "Consultez les graines de vos tirages, ou choisissez votre propre graine client."),
+    description_localized("es-ES", "Mira las semillas de tus tiradas o elige tu propia semilla de cliente.")
+)]
+pub async fn fairness(
+    ctx: Context<'_>,
+    #[description = "A new client seed to mix into your next spins"]
+    #[description_localized("fr", "Une nouvelle graine client pour vos prochains tirages")]
+    #[description_localized("es-ES", "Una nueva semilla de cliente para tus próximas tiradas")]
+    client_seed: Option<String>,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+
+    if let Some(client_seed) = &client_seed {
+        if client_seed.is_empty()
+            || client_seed.len() > MAX_CLIENT_SEED_LENGTH
+            || !client_seed.chars().all(|c| c.is_ascii_graphic())
+        {
+            return Err(Error::from(format!(
+                "Client seeds need to be 1 to {} printable characters with no spaces.",
+                MAX_CLIENT_SEED_LENGTH
+            )));
+        }
+        set_client_seed(user_id, client_seed)
+            .map_err(|_| Error::from("Sorry, I couldn't save your client seed."))?;
+    }
+
+    let commitment = get_commitment(user_id).map_err(|_| Error::from("Sorry, I couldn't look up your seeds."))?;
+
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title("🔐 Provably Fair Seeds")
+        .description("The server seed is kept secret until you reveal it with `/verify`, but its hash is fixed now, so it can't be swapped out after you've played.")
+        .footer(CreateEmbedFooter::new(DERIVATION))
+        .fields([
+            ("Server Seed Hash (SHA-256)", format!("`{}`", commitment.server_seed_hash), false),
+            ("Client Seed", format!("`{}`", commitment.client_seed), true),
+            ("Next Nonce", commitment.next_nonce.to_string(), true),
+        ]);
+
+    ctx.send(CreateReply {
+        embeds: vec![embed],
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Reveal your current server seed, check your spins against it and start a new one."),
+    description_localized("fr", "Révélez votre graine serveur, vérifiez vos tirages et commencez-en une nouvelle."),
+    description_localized("es-ES", "Revela tu semilla de servidor, comprueba tus tiradas y empieza una nueva.")
+)]
+pub async fn verify(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+    let revealed = rotate_server_seed(user_id).map_err(|_| Error::from("Sorry, I couldn't reveal your server seed."))?;
+    let spins = spins_for_server_seed(&revealed.server_seed_hash, VERIFIED_SPINS_SHOWN)
+        .map_err(|_| Error::from("Sorry, I couldn't look up the spins played with that seed."))?;
+
+    let spin_lines: Vec<String> = spins
+        .iter()
+        .map(|spin| {
+            let derived_seed = format_seed(derive_spin_seed(&revealed.server_seed, &spin.client_seed, spin.nonce));
+            if derived_seed != spin.seed {
+                return format!("❌ #{} seed {} doesn't match {}", spin.nonce, spin.seed, derived_seed);
+            }
+
+            let Ok(slot_machine) = ctx.data().slot_machines.get(Some(&spin.machine_name)) else {
+                return format!(
+                    "✅ #{} seed matches; {} is no longer on the floor to replay it (wager {})",
+                    spin.nonce, spin.machine_name, spin.wager_id
+                );
+            };
+            let slot_machine = slot_machine.lock().unwrap();
+            // Replaying on a machine that has changed since would only show how the new one pays.
+            let Some(seed) = u64::from_str_radix(&spin.seed, 16)
+                .ok()
+                .filter(|_| spin.definition_hash.as_deref() == Some(slot_machine.definition_hash()))
+            else {
+                return format!(
+                    "✅ #{} seed matches; {} has changed since, so it can't be replayed (wager {})",
+                    spin.nonce, spin.machine_name, spin.wager_id
+                );
+            };
+
+            let kind = if spin.free_spin { SpinKind::Free } else { SpinKind::Paid };
+            let replayed_payout = slot_machine.replay(seed, spin.jackpot_before, kind).payout as u64 * spin.bet as u64;
+            if replayed_payout == spin.payout {
+                format!("✅ #{} {} paid {} (wager {})", spin.nonce, spin.machine_name, spin.payout, spin.wager_id)
+            } else {
+                format!(
+                    "❌ #{} {} paid {} but replays as {} (wager {})",
+                    spin.nonce, spin.machine_name, spin.payout, replayed_payout, spin.wager_id
+                )
+            }
+        })
+        .collect();
+    let spin_summary = if spin_lines.is_empty() {
+        "No spins were played with this seed.".to_string()
+    } else {
+        spin_lines.join("\n")
+    };
+
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title("🔓 Server Seed Revealed")
+        .description("Hash the server seed with SHA-256 and compare it to the hash you were shown before playing. A new server seed is already in place for your next spins.")
+        .footer(CreateEmbedFooter::new(DERIVATION))
+        .fields([
+            ("Server Seed", format!("`{}`", revealed.server_seed), false),
+            ("Server Seed Hash (SHA-256)", format!("`{}`", revealed.server_seed_hash), false),
+            ("Client Seed", format!("`{}`", revealed.client_seed), true),
+            ("Spins Played", revealed.nonces_used.to_string(), true),
+        ])
+        .field("Latest Spins", spin_summary, false);
+
+    ctx.send(CreateReply {
+        embeds: vec![embed],
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::services::guild_settings::{jackpot_channel, set_jackpot_channel};
+use crate::services::hall_of_fame::{biggest_jackpot_hits, JackpotHit};
+use crate::{Context, Error};
+use chrono::DateTime;
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use serenity::builder::{CreateEmbed, CreateMessage};
+use tracing::error;
+
+const HALL_OF_FAME_SIZE: usize = 10;
+
+/// Posts a jackpot hit to the guild's announcement channel, if it has one.
+pub async fn announce_jackpot(ctx: Context<'_>, hit: &JackpotHit) {
+    let Some(guild_id) = hit.guild_id else {
+        return;
+    };
+    let channel_id = match jackpot_channel(guild_id) {
+        Ok(Some(channel_id)) => channel_id,
+        Ok(None) => return,
+        Err(reason) => {
+            error!("Couldn't look up the jackpot channel for guild {guild_id}: {reason:?}");
+            return;
+        }
+    };
+
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(format!("🎉 {} Hit! 🎉", hit.jackpot))
+        .description(format!(
+            "<@{}> just won **{:.0} Libcoin** on {}!",
+            hit.user_id, hit.amount, hit.machine_name
+        ));
+    if let Err(reason) = serenity::ChannelId::new(channel_id)
+        .send_message(ctx.http(), CreateMessage::new().embed(embed))
+        .await
+    {
+        error!("Couldn't announce a jackpot in channel {channel_id}: {reason:?}");
+    }
+}
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "See the biggest jackpots ever won here."),
+    description_localized("fr", "Consultez les plus gros jackpots jamais remportés ici."),
+    description_localized("es-ES", "Mira los mayores botes ganados aquí.")
+)]
+pub async fn halloffame(ctx: Context<'_>) -> Result<(), Error> {
+    // In a server only its own winners are listed, so every mention resolves to somebody there.
+    let guild_id = ctx.guild_id().map(|guild_id| guild_id.get());
+    let hits = biggest_jackpot_hits(guild_id, HALL_OF_FAME_SIZE)
+        .map_err(|_| Error::from("Sorry, I couldn't dig up the hall of fame."))?;
+
+    let description = if hits.is_empty() {
+        "Nobody has hit a jackpot yet. It could be you!".to_string()
+    } else {
+        hits.iter()
+            .enumerate()
+            .map(|(index, hit)| {
+                let when = match DateTime::parse_from_rfc3339(&hit.hit_at) {
+                    Ok(hit_at) => format!("<t:{}:d>", hit_at.timestamp()),
+                    Err(_) => "some time ago".to_string(),
+                };
+                format!(
+                    "**{}.** <@{}>: {:.0} Libcoin, {} on {} ({})",
+                    index + 1,
+                    hit.user_id,
+                    hit.amount,
+                    hit.jackpot,
+                    hit.machine_name,
+                    when
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title("🏆 Hall of Fame")
+        .description(description);
+
+    ctx.send(CreateReply {
+        embeds: vec![embed],
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    description_localized("en-US", "Pick the channel jackpot hits are announced in, or leave it out to stop announcing them."),
+    description_localized("fr", "Choisissez le salon où annoncer les jackpots, ou laissez vide pour ne plus les annoncer."),
+    description_localized("es-ES", "Elige el canal donde se anuncian los botes, o déjalo vacío para dejar de anunciarlos.")
+)]
+pub async fn jackpotchannel(
+    ctx: Context<'_>,
+    #[description = "Where to announce jackpot hits"]
+    #[description_localized("fr", "Le salon où annoncer les jackpots")]
+    #[description_localized("es-ES", "El canal donde anunciar los botes")]
+    #[channel_types("Text")]
+    channel: Option<serenity::ChannelId>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(|| Error::from("Jackpot announcements can only be set up in a server."))?;
+    set_jackpot_channel(guild_id.get(), channel.map(|channel| channel.get()))
+        .map_err(|_| Error::from("Sorry, I couldn't save the jackpot channel."))?;
+
+    let content = match channel {
+        Some(channel) => format!("Jackpot hits will be announced in <#{}>.", channel.get()),
+        None => "Jackpot hits won't be announced anymore.".to_string(),
+    };
+    ctx.send(CreateReply {
+        content: Some(content),
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
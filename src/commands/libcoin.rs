@@ -1,4 +1,3 @@
-use crate::services::libcoin::get_libcoin_balance;
 use crate::{Context, Error};
 use poise::CreateReply;
 
@@ -11,7 +10,10 @@ use poise::CreateReply;
 pub async fn balance(ctx: Context<'_>) -> Result<(), Error> {
     let user_id: u64 = ctx.author().id.get();
 
-    let balance = get_libcoin_balance(user_id)
+    let balance = ctx
+        .data()
+        .bank
+        .get_libcoin_balance(user_id)
         .await
         .map_err(|e| Error::from(format!("Failed to get libcoin balance: {}", e)))?;
 
@@ -1,9 +1,19 @@
 use crate::{Data, Error};
 use poise::Command;
 
+pub mod blackjack;
+pub mod buttons;
+pub mod cards;
+pub mod craps;
+pub mod duel;
+pub mod fairness;
+pub mod hall_of_fame;
 pub mod info;
 pub mod slot_machine;
 pub mod libcoin;
+pub mod roulette;
+pub mod sessions;
+pub mod video_poker;
 
 pub fn get_commands() -> Vec<Command<Data, Error>> {
     vec![
@@ -12,5 +22,19 @@ pub fn get_commands() -> Vec<Command<Data, Error>> {
         slot_machine::slots::paytable(),
         libcoin::balance(),
         slot_machine::slots::stats(),
+        slot_machine::slots::jackpots(),
+        slot_machine::autoplay::autoplay(),
+        fairness::fairness(),
+        fairness::verify(),
+        hall_of_fame::halloffame(),
+        hall_of_fame::jackpotchannel(),
+        blackjack::table::blackjack(),
+        roulette::table::roulette(),
+        roulette::multiplayer::roulettetable(),
+        roulette::multiplayer::roulettebet(),
+        video_poker::table::videopoker(),
+        craps::table::craps(),
+        craps::table::crapsbet(),
+        duel::duel(),
     ]
 }
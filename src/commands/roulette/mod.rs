@@ -0,0 +1,6 @@
+pub mod multiplayer;
+#[allow(clippy::module_inception)]
+pub mod roulette;
+pub mod table;
+
+pub use roulette::*;
@@ -0,0 +1,385 @@
+use super::table::{settle_bets, stake_bets};
+use super::{max_payout, parse_bets, total_stake, PlacedBet, Pocket, Wheel, MAX_BET, MAX_BETS_PER_SPIN};
+use crate::services::libcoin::LibcoinBank;
+use crate::services::settlement::{call_off, PayoutStatus, WagerId};
+use crate::{Context, Error};
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use serenity::builder::{
+    CreateActionRow, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse, EditMessage,
+};
+use std::time::Duration;
+use tracing::error;
+
+const DEFAULT_BETTING_SECONDS: u32 = 30;
+const DEFAULT_CHIP: u32 = 10;
+// Discord cuts embed descriptions off at 4096 characters; this leaves room for the text around the bets.
+const MAX_BETS_LENGTH: usize = 3500;
+
+// The outside bets, plus the zeros, offered straight from the table's menu.
+const QUICK_BETS: [(&str, &str); 13] = [
+    ("Red", "red"),
+    ("Black", "black"),
+    ("Odd", "odd"),
+    ("Even", "even"),
+    ("1-18", "low"),
+    ("19-36", "high"),
+    ("Dozen 1-12", "dozen 1"),
+    ("Dozen 13-24", "dozen 2"),
+    ("Dozen 25-36", "dozen 3"),
+    ("Column 1", "column 1"),
+    ("Column 2", "column 2"),
+    ("Column 3", "column 3"),
+    ("0", "0"),
+];
+
+/// Bets one player put down in one go, staked as a single wager.
+struct TableStake {
+    user_id: u64,
+    wager_id: WagerId,
+    bets: Vec<PlacedBet>,
+}
+
+/// A roulette round open to everyone in a channel: bets are taken until `closes_at`, then the
+/// wheel spins once for all of them.
+pub struct RouletteRound {
+    wheel: Wheel,
+    chip: u32,
+    closes_at: i64,
+    message: Option<(serenity::ChannelId, serenity::MessageId)>,
+    open: bool,
+    stakes: Vec<TableStake>,
+}
+
+impl RouletteRound {
+    /// Stakes `text`'s bets for `user_id`, returning what to tell them.
+    async fn place_bets(&mut self, bank: &dyn LibcoinBank, user_id: u64, text: &str) -> Result<String, Error> {
+        if !self.open {
+            return Err(Error::from("Bets are closed, the wheel is already spinning."));
+        }
+        let bets = parse_bets(text, self.wheel)?;
+        let already_placed: usize = self
+            .stakes
+            .iter()
+            .filter(|stake| stake.user_id == user_id)
+            .map(|stake| stake.bets.len())
+            .sum();
+        if already_placed + bets.len() > MAX_BETS_PER_SPIN {
+            return Err(Error::from(format!("You can have up to {} bets on a spin.", MAX_BETS_PER_SPIN)));
+        }
+
+        let mut table_bets: Vec<PlacedBet> = self.stakes.iter().flat_map(|stake| stake.bets.iter().cloned()).collect();
+        table_bets.extend(bets.iter().cloned());
+        let wager_id = stake_bets(bank, user_id, &bets, max_payout(&table_bets, self.wheel)).await?;
+
+        let confirmation = format!("You're on {}. Good luck!", describe_stake(&bets));
+        self.stakes.push(TableStake { user_id, wager_id, bets });
+
+        Ok(confirmation)
+    }
+
+    /// Each player's bets, in the order they sat down.
+    fn players(&self) -> Vec<(u64, Vec<&PlacedBet>)> {
+        let mut players: Vec<(u64, Vec<&PlacedBet>)> = Vec::new();
+        for stake in &self.stakes {
+            match players.iter_mut().find(|(user_id, _)| *user_id == stake.user_id) {
+                Some((_, bets)) => bets.extend(&stake.bets),
+                None => players.push((stake.user_id, stake.bets.iter().collect())),
+            }
+        }
+
+        players
+    }
+
+    fn embed(&self) -> CreateEmbed {
+        let lines: Vec<String> = self
+            .players()
+            .into_iter()
+            .map(|(user_id, bets)| {
+                let bets: Vec<String> = bets.iter().map(|bet| format!("{} {}", bet.bet.name, bet.amount)).collect();
+                format!("<@{}>: {}", user_id, bets.join(", "))
+            })
+            .collect();
+        let bets = if lines.is_empty() {
+            "No bets yet.".to_string()
+        } else {
+            fit_lines(&lines)
+        };
+
+        CreateEmbed::new()
+            .color(0x5b9e48)
+            .title(format!("🎡 {} Roulette Table", self.wheel.title()))
+            .description(format!(
+                "Bets close <t:{}:R>. Pick a spot below to put a {} Libcoin chip on it, or use `/roulettebet` for anything else, e.g. `17 5, split 8-11 2`.\n\n{}",
+                self.closes_at, self.chip, bets
+            ))
+            .footer(CreateEmbedFooter::new(
+                "Straight 35:1 · Split 17:1 · Street 11:1 · Corner 8:1 · Line 5:1 · Dozen/Column 2:1 · Even money 1:1",
+            ))
+    }
+
+    /// Shows the latest bets on the table's message.
+    async fn refresh(&self, ctx: Context<'_>) {
+        let Some((channel_id, message_id)) = self.message else {
+            return;
+        };
+        if let Err(reason) = channel_id
+            .edit_message(ctx.http(), message_id, EditMessage::new().embed(self.embed()))
+            .await
+        {
+            error!("Couldn't update the roulette table in channel {channel_id}: {reason:?}");
+        }
+    }
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Open a roulette table everyone in the channel can bet on before the wheel spins."),
+    description_localized("fr", "Ouvrez une table de roulette où tout le salon peut miser avant que la roue tourne."),
+    description_localized("es-ES", "Abre una mesa de ruleta en la que todo el canal puede apostar antes de que gire la rueda.")
+)]
+pub async fn roulettetable(
+    ctx: Context<'_>,
+    #[description = "Single-zero European wheel (default) or double-zero American wheel"]
+    #[description_localized("fr", "Roue européenne à un zéro (par défaut) ou américaine à double zéro")]
+    #[description_localized("es-ES", "Rueda europea de un cero (por defecto) o americana de doble cero")]
+    wheel: Option<Wheel>,
+    #[description = "How long bets are taken for, in seconds"]
+    #[description_localized("fr", "Combien de secondes les mises restent ouvertes")]
+    #[description_localized("es-ES", "Cuántos segundos se aceptan apuestas")]
+    #[min = 15]
+    #[max = 120]
+    seconds: Option<u32>,
+    #[description = "How much each pick from the table's menu puts down"]
+    #[description_localized("fr", "Combien chaque choix du menu de la table mise")]
+    #[description_localized("es-ES", "Cuánto apuesta cada elección del menú de la mesa")]
+    #[min = 1]
+    chip: Option<u32>,
+) -> Result<(), Error> {
+    let channel_id = ctx.channel_id();
+    let wheel = wheel.unwrap_or(Wheel::European);
+    let seconds = seconds.unwrap_or(DEFAULT_BETTING_SECONDS);
+    let chip = chip.unwrap_or(DEFAULT_CHIP);
+    if chip > MAX_BET {
+        return Err(Error::from(format!("The table takes up to {} Libcoin on one bet.", MAX_BET)));
+    }
+    let round = ctx
+        .data()
+        .roulette_tables
+        .open(
+            channel_id.get(),
+            RouletteRound {
+                wheel,
+                chip,
+                closes_at: Utc::now().timestamp() + seconds as i64,
+                message: None,
+                open: true,
+                stakes: Vec::new(),
+            },
+        )
+        .ok_or_else(|| Error::from("There's already a roulette table open in this channel. Get your bets in!"))?;
+    let betting_closes = tokio::time::Instant::now() + Duration::from_secs(seconds as u64);
+
+    let select_id = format!("{}-roulette-table", ctx.id());
+    let options: Vec<CreateSelectMenuOption> = QUICK_BETS
+        .iter()
+        .chain((wheel == Wheel::American).then_some(&("00", "00")))
+        .map(|(label, bet)| CreateSelectMenuOption::new(*label, *bet))
+        .collect();
+    let menu = CreateSelectMenu::new(&select_id, CreateSelectMenuKind::String { options })
+        .placeholder(format!("Put a {} Libcoin chip on...", chip));
+
+    let opened = {
+        let mut round = round.lock().await;
+        let reply = CreateReply {
+            embeds: vec![round.embed()],
+            components: Some(vec![CreateActionRow::SelectMenu(menu)]),
+            ..Default::default()
+        };
+        match ctx.send(reply).await {
+            Ok(handle) => handle.message().await.map(|message| {
+                round.message = Some((message.channel_id, message.id));
+            }),
+            Err(reason) => Err(reason),
+        }
+    };
+    if let Err(reason) = opened {
+        // Without a table on screen nobody can see the round, so it's called off.
+        let mut round = round.lock().await;
+        round.open = false;
+        ctx.data().roulette_tables.close(channel_id.get());
+        refund_stakes(ctx.data().bank.as_ref(), &round.stakes).await;
+        return Err(reason.into());
+    }
+
+    while let Some(interaction) = serenity::ComponentInteractionCollector::new(ctx)
+        .channel_id(channel_id)
+        .custom_ids(vec![select_id.clone()])
+        .timeout(betting_closes.saturating_duration_since(tokio::time::Instant::now()))
+        .await
+    {
+        let serenity::ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind else {
+            continue;
+        };
+        let defer = CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new().ephemeral(true));
+        if let Err(reason) = interaction.create_response(ctx, defer).await {
+            error!("Couldn't acknowledge a roulette table bet: {reason:?}");
+            continue;
+        }
+
+        let text: Vec<String> = values.iter().map(|bet| format!("{} {}", bet, chip)).collect();
+        let placed = {
+            let mut round = round.lock().await;
+            let placed = round.place_bets(ctx.data().bank.as_ref(), interaction.user.id.get(), &text.join(", ")).await;
+            if placed.is_ok() {
+                round.refresh(ctx).await;
+            }
+            placed
+        };
+        let content = placed.unwrap_or_else(|reason| reason.to_string());
+        if let Err(reason) = interaction.edit_response(ctx, EditInteractionResponse::new().content(content)).await {
+            error!("Couldn't answer a roulette table bet: {reason:?}");
+        }
+    }
+
+    // Bets still being staked get to finish before the wheel goes.
+    let mut round = round.lock().await;
+    round.open = false;
+    ctx.data().roulette_tables.close(channel_id.get());
+
+    let pocket = wheel.spin(&mut rand::rng());
+    // Each player's total staked, won, and refunded because their wager couldn't be settled.
+    let mut results: Vec<(u64, u64, u64, u64)> = Vec::new();
+    let mut any_queued = false;
+    for stake in &round.stakes {
+        let staked = total_stake(&stake.bets);
+        let (payout, refunded) = match settle_bets(ctx.data().bank.as_ref(), stake.wager_id, stake.user_id, &stake.bets, pocket).await {
+            Ok((payout, payout_status)) => {
+                any_queued |= payout_status == PayoutStatus::Queued;
+                (payout, 0)
+            }
+            Err(_) => {
+                call_off(ctx.data().bank.as_ref(), stake.wager_id).await;
+                (0, staked)
+            }
+        };
+        match results.iter_mut().find(|(user_id, _, _, _)| *user_id == stake.user_id) {
+            Some((_, player_staked, player_payout, player_refunded)) => {
+                *player_staked += staked;
+                *player_payout += payout;
+                *player_refunded += refunded;
+            }
+            None => results.push((stake.user_id, staked, payout, refunded)),
+        }
+    }
+
+    let final_embed = results_embed(&round, pocket, &results, any_queued);
+    if let Some((channel_id, message_id)) = round.message {
+        channel_id
+            .edit_message(ctx.http(), message_id, EditMessage::new().embed(final_embed).components(Vec::new()))
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Put bets on the roulette table open in this channel."),
+    description_localized("fr", "Misez sur la table de roulette ouverte dans ce salon."),
+    description_localized("es-ES", "Apuesta en la mesa de ruleta abierta en este canal.")
+)]
+pub async fn roulettebet(
+    ctx: Context<'_>,
+    #[description = "Your bets and how much on each, e.g. red 10, 17 5, split 8-11 2, dozen 2 20"]
+    #[description_localized("fr", "Vos mises et leur montant, p. ex. red 10, 17 5, split 8-11 2, dozen 2 20")]
+    #[description_localized("es-ES", "Tus apuestas y cuánto a cada una, p. ej. red 10, 17 5, split 8-11 2, dozen 2 20")]
+    bets: String,
+) -> Result<(), Error> {
+    let round = ctx
+        .data()
+        .roulette_tables
+        .get(ctx.channel_id().get())
+        .ok_or_else(|| Error::from("There's no roulette table open in this channel. Open one with `/roulettetable`."))?;
+
+    let confirmation = {
+        let mut round = round.lock().await;
+        let confirmation = round.place_bets(ctx.data().bank.as_ref(), ctx.author().id.get(), &bets).await?;
+        round.refresh(ctx).await;
+        confirmation
+    };
+    ctx.send(CreateReply {
+        content: Some(confirmation),
+        ephemeral: Some(true),
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
+
+async fn refund_stakes(bank: &dyn LibcoinBank, stakes: &[TableStake]) {
+    for stake in stakes {
+        call_off(bank, stake.wager_id).await;
+    }
+}
+
+fn results_embed(round: &RouletteRound, pocket: Pocket, results: &[(u64, u64, u64, u64)], any_queued: bool) -> CreateEmbed {
+    let lines: Vec<String> = results
+        .iter()
+        .map(|&(user_id, staked, payout, refunded)| {
+            if refunded == staked {
+                return format!("<@{}> staked {}, but I couldn't settle it, so it's on its way back", user_id, staked);
+            }
+            let outcome = match (payout, refunded) {
+                (0, 0) => format!("<@{}> staked {} and lost it", user_id, staked),
+                (0, refunded) => format!("<@{}> staked {} and lost {}", user_id, staked, staked - refunded),
+                (payout, _) => format!("<@{}> staked {} and **won {}**", user_id, staked, payout),
+            };
+            match refunded {
+                0 => outcome,
+                refunded => format!("{}; {} I couldn't settle is on its way back", outcome, refunded),
+            }
+        })
+        .collect();
+    let mut description = format!("The ball lands on {} **{}**", pocket.emoji(), pocket);
+    if lines.is_empty() {
+        description.push_str("\n\nNobody placed a bet this round.");
+    } else {
+        description.push_str(&format!("\n\n{}", fit_lines(&lines)));
+    }
+    if any_queued {
+        description.push_str("\n\nThe bank is slow to answer, some winnings will arrive shortly.");
+    }
+
+    CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(format!("🎡 {} Roulette Table", round.wheel.title()))
+        .description(description)
+}
+
+fn describe_stake(bets: &[PlacedBet]) -> String {
+    bets.iter()
+        .map(|bet| format!("{} for {}", bet.bet.name, bet.amount))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Joins as many of `lines` as fit in an embed description, noting how many were left out.
+fn fit_lines(lines: &[String]) -> String {
+    let mut shown = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        if shown.len() + line.len() + 1 > MAX_BETS_LENGTH {
+            shown.push_str(&format!("...and {} more", lines.len() - index));
+            break;
+        }
+        shown.push_str(line);
+        shown.push('\n');
+    }
+
+    shown.trim_end().to_string()
+}
@@ -0,0 +1,387 @@
+use crate::Error;
+use rand::Rng;
+use std::fmt;
+
+/// Most bets one player can have on a single spin.
+pub const MAX_BETS_PER_SPIN: usize = 10;
+/// Most Libcoin one bet can carry, so a spin's stakes and payouts stay well inside `u64`.
+pub const MAX_BET: u32 = 100_000;
+
+const RED_NUMBERS: [u8; 18] = [1, 3, 5, 7, 9, 12, 14, 16, 18, 19, 21, 23, 25, 27, 30, 32, 34, 36];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum Wheel {
+    #[name = "European (single zero)"]
+    European,
+    #[name = "American (double zero)"]
+    American,
+}
+
+impl Wheel {
+    pub fn title(self) -> &'static str {
+        match self {
+            Wheel::European => "European",
+            Wheel::American => "American",
+        }
+    }
+
+    pub fn pockets(self) -> Vec<Pocket> {
+        let zeros = match self {
+            Wheel::European => vec![Pocket::Zero],
+            Wheel::American => vec![Pocket::Zero, Pocket::DoubleZero],
+        };
+        zeros.into_iter().chain((1..=36).map(Pocket::Number)).collect()
+    }
+
+    pub fn spin(self, rng: &mut impl Rng) -> Pocket {
+        let pockets = self.pockets();
+        pockets[rng.random_range(0..pockets.len())]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pocket {
+    Zero,
+    /// Only on American wheels.
+    DoubleZero,
+    Number(u8),
+}
+
+impl Pocket {
+    fn number(self) -> Option<u8> {
+        match self {
+            Pocket::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    pub fn is_red(self) -> bool {
+        self.number().is_some_and(|number| RED_NUMBERS.contains(&number))
+    }
+
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Pocket::Zero | Pocket::DoubleZero => "🟢",
+            _ if self.is_red() => "🔴",
+            _ => "⚫",
+        }
+    }
+}
+
+impl fmt::Display for Pocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pocket::Zero => write!(f, "0"),
+            Pocket::DoubleZero => write!(f, "00"),
+            Pocket::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BetKind {
+    Straight,
+    Split,
+    Street,
+    Corner,
+    Line,
+    Dozen,
+    Column,
+    Red,
+    Black,
+    Odd,
+    Even,
+    Low,
+    High,
+}
+
+impl BetKind {
+    /// Paid to one, on top of the stake coming back.
+    pub fn odds(self) -> u64 {
+        match self {
+            BetKind::Straight => 35,
+            BetKind::Split => 17,
+            BetKind::Street => 11,
+            BetKind::Corner => 8,
+            BetKind::Line => 5,
+            BetKind::Dozen | BetKind::Column => 2,
+            BetKind::Red | BetKind::Black | BetKind::Odd | BetKind::Even | BetKind::Low | BetKind::High => 1,
+        }
+    }
+}
+
+/// Somewhere on the layout to put chips, and the pockets it wins on.
+#[derive(Debug, Clone)]
+pub struct Bet {
+    pub kind: BetKind,
+    pub name: String,
+    pub pockets: Vec<Pocket>,
+}
+
+impl Bet {
+    pub fn covers(&self, pocket: Pocket) -> bool {
+        self.pockets.contains(&pocket)
+    }
+
+    /// Reads bets written the way they'd be called out at the table: `17`, `00`, `split 8-11`,
+    /// `street 7`, `corner 8`, `line 1`, `dozen 2`, `column 3`, `red`, `black`, `odd`, `even`,
+    /// `low` (1-18) and `high` (19-36).
+    pub fn parse(text: &str, wheel: Wheel) -> Result<Bet, Error> {
+        let text = text.trim().to_lowercase();
+        let (keyword, target) = match text.split_once(char::is_whitespace) {
+            Some((keyword, target)) => (keyword, target.trim()),
+            None => (text.as_str(), ""),
+        };
+        let unknown = || Error::from(format!("I don't know the bet \"{}\".", text));
+        let numbers = |filter: &dyn Fn(u8) -> bool| -> Vec<Pocket> { (1..=36).filter(|n| filter(*n)).map(Pocket::Number).collect() };
+
+        let bet = match (keyword, target) {
+            ("red", "") => Bet::new(BetKind::Red, "Red", numbers(&|n| RED_NUMBERS.contains(&n))),
+            ("black", "") => Bet::new(BetKind::Black, "Black", numbers(&|n| !RED_NUMBERS.contains(&n))),
+            ("odd", "") => Bet::new(BetKind::Odd, "Odd", numbers(&|n| n % 2 == 1)),
+            ("even", "") => Bet::new(BetKind::Even, "Even", numbers(&|n| n % 2 == 0)),
+            ("low" | "1-18", "") => Bet::new(BetKind::Low, "1-18", numbers(&|n| n <= 18)),
+            ("high" | "19-36", "") => Bet::new(BetKind::High, "19-36", numbers(&|n| n >= 19)),
+            ("dozen", index @ ("1" | "2" | "3")) => {
+                let first = (index.parse::<u8>()? - 1) * 12 + 1;
+                let name = format!("Dozen {}-{}", first, first + 11);
+                Bet::new(BetKind::Dozen, name, numbers(&|n| (first..first + 12).contains(&n)))
+            }
+            ("column", index @ ("1" | "2" | "3")) => {
+                let column = index.parse::<u8>()?;
+                Bet::new(BetKind::Column, format!("Column {}", column), numbers(&|n| (n - 1) % 3 + 1 == column))
+            }
+            ("street", number) => {
+                let first = street_start(parse_number(number).ok_or_else(unknown)?);
+                Bet::new(BetKind::Street, format!("Street {}-{}", first, first + 2), numbers(&|n| (first..first + 3).contains(&n)))
+            }
+            ("line", number) => {
+                let first = street_start(parse_number(number).ok_or_else(unknown)?);
+                if first > 31 {
+                    return Err(Error::from("A line is two streets next to each other; name the first one, 1 to 31."));
+                }
+                Bet::new(BetKind::Line, format!("Line {}-{}", first, first + 5), numbers(&|n| (first..first + 6).contains(&n)))
+            }
+            ("corner", number) => {
+                let first = parse_number(number).ok_or_else(unknown)?;
+                if first % 3 == 0 || first > 32 {
+                    return Err(Error::from(
+                        "A corner is named by its lowest number, which can't be in the third column or past 32.",
+                    ));
+                }
+                let corner = [first, first + 1, first + 3, first + 4];
+                let name = format!("Corner {}/{}/{}/{}", corner[0], corner[1], corner[2], corner[3]);
+                Bet::new(BetKind::Corner, name, corner.into_iter().map(Pocket::Number).collect())
+            }
+            ("split", pair) => {
+                let (first, second) = pair.split_once(['-', '/']).ok_or_else(unknown)?;
+                let first = parse_pocket(first.trim(), wheel).ok_or_else(unknown)?;
+                let second = parse_pocket(second.trim(), wheel).ok_or_else(unknown)?;
+                if !are_adjacent(first, second, wheel) {
+                    return Err(Error::from(format!("{} and {} aren't next to each other on the layout.", first, second)));
+                }
+                Bet::new(BetKind::Split, format!("Split {}/{}", first, second), vec![first, second])
+            }
+            (pocket, "") => {
+                let pocket = parse_pocket(pocket, wheel).ok_or_else(unknown)?;
+                Bet::new(BetKind::Straight, pocket.to_string(), vec![pocket])
+            }
+            _ => return Err(unknown()),
+        };
+
+        Ok(bet)
+    }
+
+    fn new(kind: BetKind, name: impl Into<String>, pockets: Vec<Pocket>) -> Self {
+        Bet {
+            kind,
+            name: name.into(),
+            pockets,
+        }
+    }
+}
+
+/// A bet with chips on it.
+#[derive(Debug, Clone)]
+pub struct PlacedBet {
+    pub bet: Bet,
+    pub amount: u32,
+}
+
+impl PlacedBet {
+    /// What comes back if the ball lands in `pocket`, the stake included.
+    pub fn payout(&self, pocket: Pocket) -> u64 {
+        if self.bet.covers(pocket) {
+            self.amount as u64 * (self.bet.kind.odds() + 1)
+        } else {
+            0
+        }
+    }
+}
+
+/// Reads a comma-separated list of bets, each followed by how much to put on it, such as
+/// `red 10, 17 5, split 8-11 2`.
+pub fn parse_bets(text: &str, wheel: Wheel) -> Result<Vec<PlacedBet>, Error> {
+    let mut bets = Vec::new();
+    for entry in text.split([',', ';']).map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (target, amount) = entry
+            .rsplit_once(char::is_whitespace)
+            .ok_or_else(|| Error::from(format!("How much do you want on \"{}\"? Put the amount after the bet.", entry)))?;
+        let amount = match amount.parse::<u32>() {
+            Ok(amount) if amount > MAX_BET => {
+                return Err(Error::from(format!("The table takes up to {} Libcoin on one bet.", MAX_BET)))
+            }
+            Ok(amount) if amount > 0 => amount,
+            _ => return Err(Error::from(format!("\"{}\" isn't an amount of Libcoin you can bet.", amount))),
+        };
+        bets.push(PlacedBet {
+            bet: Bet::parse(target, wheel)?,
+            amount,
+        });
+    }
+
+    if bets.is_empty() {
+        return Err(Error::from("Place at least one bet, e.g. `red 10, 17 5`."));
+    }
+    if bets.len() > MAX_BETS_PER_SPIN {
+        return Err(Error::from(format!("You can have up to {} bets on a spin.", MAX_BETS_PER_SPIN)));
+    }
+
+    Ok(bets)
+}
+
+/// What `bets` put on the table between them.
+pub fn total_stake(bets: &[PlacedBet]) -> u64 {
+    bets.iter().map(|bet| bet.amount as u64).sum()
+}
+
+/// Everything `bets` pay if the ball lands in `pocket`, stakes included.
+pub fn total_payout(bets: &[PlacedBet], pocket: Pocket) -> u64 {
+    bets.iter().map(|bet| bet.payout(pocket)).sum()
+}
+
+/// The most `bets` can pay out on `wheel`, whichever pocket comes up.
+pub fn max_payout(bets: &[PlacedBet], wheel: Wheel) -> u64 {
+    wheel.pockets().into_iter().map(|pocket| total_payout(bets, pocket)).max().unwrap_or(0)
+}
+
+fn parse_number(text: &str) -> Option<u8> {
+    text.parse::<u8>().ok().filter(|number| (1..=36).contains(number))
+}
+
+fn parse_pocket(text: &str, wheel: Wheel) -> Option<Pocket> {
+    match text {
+        "0" => Some(Pocket::Zero),
+        "00" if wheel == Wheel::American => Some(Pocket::DoubleZero),
+        _ => parse_number(text).map(Pocket::Number),
+    }
+}
+
+/// The first number of the street (row of three) `number` sits in.
+fn street_start(number: u8) -> u8 {
+    (number - 1) / 3 * 3 + 1
+}
+
+fn are_adjacent(first: Pocket, second: Pocket, wheel: Wheel) -> bool {
+    // Lowest number first, and zeros ahead of numbers.
+    let (first, second) = match (first, second) {
+        (Pocket::Number(a), Pocket::Number(b)) if a > b => (second, first),
+        (Pocket::Number(_), Pocket::Number(_)) => (first, second),
+        (Pocket::Number(_), _) => (second, first),
+        _ => (first, second),
+    };
+    match (wheel, first, second) {
+        (_, Pocket::Number(low), Pocket::Number(high)) => high == low + 3 || (high == low + 1 && low % 3 != 0),
+        (Wheel::European, Pocket::Zero, Pocket::Number(number)) => number <= 3,
+        (Wheel::American, Pocket::Zero, Pocket::Number(number)) => number <= 2,
+        (Wheel::American, Pocket::DoubleZero, Pocket::Number(number)) => (2..=3).contains(&number),
+        (Wheel::American, Pocket::Zero, Pocket::DoubleZero) | (Wheel::American, Pocket::DoubleZero, Pocket::Zero) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(bet: &Bet) -> Vec<u8> {
+        bet.pockets.iter().filter_map(|pocket| pocket.number()).collect()
+    }
+
+    #[test]
+    fn outside_bets_cover_their_eighteen_or_twelve_numbers() {
+        let red = Bet::parse("red", Wheel::European).unwrap();
+        assert_eq!(numbers(&red), RED_NUMBERS);
+        let black = Bet::parse("Black", Wheel::European).unwrap();
+        assert_eq!(black.pockets.len(), 18);
+        assert!(red.pockets.iter().all(|pocket| !black.covers(*pocket)));
+        assert_eq!(numbers(&Bet::parse("low", Wheel::European).unwrap()), (1..=18).collect::<Vec<_>>());
+        assert_eq!(numbers(&Bet::parse("19-36", Wheel::European).unwrap()), (19..=36).collect::<Vec<_>>());
+        assert_eq!(numbers(&Bet::parse("dozen 2", Wheel::European).unwrap()), (13..=24).collect::<Vec<_>>());
+        assert_eq!(numbers(&Bet::parse("column 2", Wheel::European).unwrap()), (2..=35).step_by(3).collect::<Vec<_>>());
+        for zero in ["red", "black", "odd", "even", "low", "high", "dozen 1", "column 1"] {
+            let bet = Bet::parse(zero, Wheel::American).unwrap();
+            assert!(!bet.covers(Pocket::Zero) && !bet.covers(Pocket::DoubleZero), "{zero} covers a zero");
+        }
+    }
+
+    #[test]
+    fn inside_bets_are_named_from_their_lowest_number() {
+        let street = Bet::parse("street 8", Wheel::European).unwrap();
+        assert_eq!((street.kind, numbers(&street)), (BetKind::Street, vec![7, 8, 9]));
+        let line = Bet::parse("line 31", Wheel::European).unwrap();
+        assert_eq!(numbers(&line), (31..=36).collect::<Vec<_>>());
+        let corner = Bet::parse("corner 8", Wheel::European).unwrap();
+        assert_eq!(numbers(&corner), vec![8, 9, 11, 12]);
+        assert!(Bet::parse("line 34", Wheel::European).is_err());
+        assert!(Bet::parse("corner 9", Wheel::European).is_err());
+        assert!(Bet::parse("corner 33", Wheel::European).is_err());
+        assert!(Bet::parse("37", Wheel::European).is_err());
+        assert!(Bet::parse("00", Wheel::European).is_err());
+        assert_eq!(Bet::parse("00", Wheel::American).unwrap().pockets, vec![Pocket::DoubleZero]);
+    }
+
+    #[test]
+    fn splits_only_join_neighbours_on_the_layout() {
+        let n = Pocket::Number;
+        assert!(are_adjacent(n(8), n(11), Wheel::European));
+        assert!(are_adjacent(n(11), n(8), Wheel::European));
+        assert!(are_adjacent(n(8), n(9), Wheel::European));
+        // 3 ends a row and 4 starts the next.
+        assert!(!are_adjacent(n(3), n(4), Wheel::European));
+        assert!(!are_adjacent(n(8), n(12), Wheel::European));
+        assert!(are_adjacent(Pocket::Zero, n(3), Wheel::European));
+        assert!(!are_adjacent(Pocket::Zero, n(3), Wheel::American));
+        assert!(are_adjacent(n(2), Pocket::Zero, Wheel::American));
+        assert!(are_adjacent(Pocket::DoubleZero, n(3), Wheel::American));
+        assert!(!are_adjacent(Pocket::DoubleZero, n(1), Wheel::American));
+        assert!(are_adjacent(Pocket::Zero, Pocket::DoubleZero, Wheel::American));
+        assert!(Bet::parse("split 3-4", Wheel::European).is_err());
+        assert_eq!(Bet::parse("split 0/00", Wheel::American).unwrap().kind, BetKind::Split);
+    }
+
+    #[test]
+    fn winning_bets_return_their_odds_and_the_stake() {
+        let bets = parse_bets("17 10, red 5, split 17-20 2", Wheel::European).unwrap();
+        // 17 is black.
+        assert_eq!(total_payout(&bets, Pocket::Number(17)), 360 + 36);
+        assert_eq!(total_payout(&bets, Pocket::Number(20)), 36);
+        assert_eq!(total_payout(&bets, Pocket::Number(1)), 10);
+        assert_eq!(total_payout(&bets, Pocket::Zero), 0);
+        assert_eq!(max_payout(&bets, Wheel::European), 396);
+        assert_eq!(total_stake(&bets), 17);
+    }
+
+    #[test]
+    fn amounts_past_the_table_maximum_are_turned_away() {
+        assert!(parse_bets(&format!("red {}", MAX_BET), Wheel::European).is_ok());
+        assert!(parse_bets(&format!("red {}", MAX_BET + 1), Wheel::European).is_err());
+        assert!(parse_bets("red 2147483648, black 2147483648", Wheel::European).is_err());
+        assert!(parse_bets("red 0", Wheel::European).is_err());
+        assert!(parse_bets("red", Wheel::European).is_err());
+
+        let most = ["17 100000"; MAX_BETS_PER_SPIN].join(", ");
+        let bets = parse_bets(&most, Wheel::European).unwrap();
+        assert_eq!(max_payout(&bets, Wheel::European), 36_000_000);
+    }
+}
@@ -0,0 +1,92 @@
+use super::{max_payout, parse_bets, total_payout, total_stake, PlacedBet, Pocket, Wheel};
+use crate::services::libcoin::LibcoinBank;
+use crate::services::settlement::{describe_payout, settle, stake_wager, PayoutStatus, WagerId};
+use crate::{Context, Error, MR_HOUSE_ID};
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+
+const ROULETTE_GAME: &str = "roulette";
+const DEDUCT_MESSAGE: &str = "Playing roulette";
+const GRANT_MESSAGE: &str = "Winning at roulette";
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Put your Libcoin on the roulette layout and spin the wheel."),
+    description_localized("fr", "Placez vos Libcoin sur le tapis de roulette et lancez la roue."),
+    description_localized("es-ES", "Pon tus Libcoin en el tapete de la ruleta y gira la rueda.")
+)]
+pub async fn roulette(
+    ctx: Context<'_>,
+    #[description = "Your bets and how much on each, e.g. red 10, 17 5, split 8-11 2, dozen 2 20"]
+    #[description_localized("fr", "Vos mises et leur montant, p. ex. red 10, 17 5, split 8-11 2, dozen 2 20")]
+    #[description_localized("es-ES", "Tus apuestas y cuánto a cada una, p. ej. red 10, 17 5, split 8-11 2, dozen 2 20")]
+    bets: String,
+    #[description = "Single-zero European wheel (default) or double-zero American wheel"]
+    #[description_localized("fr", "Roue européenne à un zéro (par défaut) ou américaine à double zéro")]
+    #[description_localized("es-ES", "Rueda europea de un cero (por defecto) o americana de doble cero")]
+    wheel: Option<Wheel>,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+    let bank = &ctx.data().bank;
+    let wheel = wheel.unwrap_or(Wheel::European);
+    let bets = parse_bets(&bets, wheel)?;
+
+    let wager_id = stake_bets(bank.as_ref(), user_id, &bets, max_payout(&bets, wheel)).await?;
+    let pocket = wheel.spin(&mut rand::rng());
+    let (payout, payout_status) = settle_bets(bank.as_ref(), wager_id, user_id, &bets, pocket).await?;
+
+    ctx.send(CreateReply {
+        embeds: vec![result_embed(wheel, pocket, &bets, payout, payout_status)],
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Takes the stakes for `bets` as one wager, once the player can afford them and the house can
+/// cover paying out `exposure`.
+pub async fn stake_bets(bank: &dyn LibcoinBank, user_id: u64, bets: &[PlacedBet], exposure: u64) -> Result<WagerId, Error> {
+    stake_wager(bank, user_id, ROULETTE_GAME, total_stake(bets) as f64, exposure as f64, DEDUCT_MESSAGE, MR_HOUSE_ID).await
+}
+
+/// Pays out whatever `bets` won on `pocket`, returning the payout.
+pub async fn settle_bets(
+    bank: &dyn LibcoinBank,
+    wager_id: WagerId,
+    user_id: u64,
+    bets: &[PlacedBet],
+    pocket: Pocket,
+) -> Result<(u64, PayoutStatus), Error> {
+    let payout = total_payout(bets, pocket);
+    let payout_status = settle(bank, wager_id, user_id, payout as f64, GRANT_MESSAGE).await?;
+
+    Ok((payout, payout_status))
+}
+
+/// One line per bet, with what it paid on `pocket`.
+pub fn describe_bets(bets: &[PlacedBet], pocket: Pocket) -> String {
+    bets.iter()
+        .map(|bet| match bet.payout(pocket) {
+            0 => format!("{} ({}): lost", bet.bet.name, bet.amount),
+            payout => format!("{} ({}): **won {}**", bet.bet.name, bet.amount, payout),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn result_embed(wheel: Wheel, pocket: Pocket, bets: &[PlacedBet], payout: u64, payout_status: PayoutStatus) -> CreateEmbed {
+    let stake = total_stake(bets);
+
+    CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(format!("🎡 {} Roulette", wheel.title()))
+        .description(format!("The ball lands on {} **{}**", pocket.emoji(), pocket))
+        .field("Bets", describe_bets(bets, pocket), false)
+        .field("Staked", stake.to_string(), true)
+        .field("Payout", describe_payout(payout, payout_status), true)
+        .footer(CreateEmbedFooter::new(
+            "Straight 35:1 · Split 17:1 · Street 11:1 · Corner 8:1 · Line 5:1 · Dozen/Column 2:1 · Even money 1:1",
+        ))
+}
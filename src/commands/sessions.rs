@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Games that belong to a channel rather than a player, at most one per channel. Each session sits
+/// behind an async lock so a command can hold it across bank calls while the game's own task waits
+/// its turn.
+pub struct ChannelSessions<T> {
+    sessions: Mutex<HashMap<u64, Arc<tokio::sync::Mutex<T>>>>,
+}
+
+impl<T> Default for ChannelSessions<T> {
+    fn default() -> Self {
+        ChannelSessions {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T> ChannelSessions<T> {
+    /// Starts `session` in `channel_id`, or returns `None` if the channel already has one going.
+    pub fn open(&self, channel_id: u64, session: T) -> Option<Arc<tokio::sync::Mutex<T>>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.contains_key(&channel_id) {
+            return None;
+        }
+        let session = Arc::new(tokio::sync::Mutex::new(session));
+        sessions.insert(channel_id, session.clone());
+
+        Some(session)
+    }
+
+    pub fn get(&self, channel_id: u64) -> Option<Arc<tokio::sync::Mutex<T>>> {
+        self.sessions.lock().unwrap().get(&channel_id).cloned()
+    }
+
+    /// Ends the channel's session. Anyone still holding it keeps their handle, so a session should
+    /// also record that it's over.
+    pub fn close(&self, channel_id: u64) {
+        self.sessions.lock().unwrap().remove(&channel_id);
+    }
+}
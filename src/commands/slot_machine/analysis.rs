@@ -0,0 +1,354 @@
+use super::slot_machine::{
+    count_in_window, line_shows_pattern, matching_rules, scatter_rules, visible_symbols, PayPattern, SlotMachine, Symbol,
+};
+use std::collections::HashMap;
+
+/// Exact figures for a machine, from enumerating every window the reels can stop on.
+#[derive(Debug, Clone)]
+pub struct PayTableAnalysis {
+    /// Long-run return to player as a fraction of the stake, counting the jackpot at its average
+    /// value when hit and the free spins a paid spin leads to on average. Progressive pools are
+    /// counted as if this machine were the only one feeding them: everything it puts in comes
+    /// back out, plus the seed each time it's won.
+    pub rtp: f64,
+    /// Chance that a single spin pays anything at all.
+    pub hit_frequency: f64,
+    /// Chance that each rule pays on a spin, on at least one line or as a scatter, in paytable
+    /// order. Rules that never pay under the machine's evaluation mode come out as zero.
+    pub rule_hit_probabilities: Vec<f64>,
+    /// Standard deviation of a single spin's payout, in multiples of the stake.
+    pub volatility: f64,
+    /// Chance that a single spin hits the jackpot.
+    pub jackpot_probability: f64,
+    /// Average number of paid spins between jackpots, counting jackpots hit on free spins.
+    pub jackpot_cycle_length: f64,
+    /// Average size of the rolling jackpot when it is hit.
+    pub expected_jackpot: f64,
+    /// The most a spin that misses the jackpot can pay.
+    pub max_payout_without_jackpot: f64,
+    /// The most the other wins on a jackpot spin can add to the jackpot itself.
+    pub max_payout_beside_jackpot: f64,
+    /// Chance that a single spin awards free spins.
+    pub bonus_probability: f64,
+    /// Average number of free spins each paid spin leads to, retriggers included. Infinite when
+    /// a bonus round would, on average, never end.
+    pub free_spins_per_spin: f64,
+    /// Chance that a single spin wins each of the machine's progressive jackpots, in order.
+    pub progressive_hit_probabilities: Vec<f64>,
+}
+
+/// What a line pays and which progressive jackpots it triggers, worked out once per set of symbols.
+struct LineScore {
+    rules: Vec<(usize, u32)>,
+    progressive_triggers: Vec<usize>,
+}
+
+/// How many combinations of distinct reel windows a machine has, which is what analysing it costs.
+pub fn window_combinations(reels: &[Vec<Symbol>], rows: usize) -> u128 {
+    reels
+        .iter()
+        .map(|strip| distinct_windows(strip, rows).len() as u128)
+        .product()
+}
+
+pub fn analyze_pay_table(slot_machine: &SlotMachine) -> PayTableAnalysis {
+    let pay_table = slot_machine.pay_table();
+    let layout = slot_machine.layout();
+    let reel_windows: Vec<Vec<(Vec<Symbol>, f64)>> = layout
+        .reels
+        .iter()
+        .map(|strip| distinct_windows(strip, layout.rows))
+        .collect();
+    let symbols = slot_machine.symbols();
+    let symbol_count = symbols.len() as u64;
+
+    let mut rule_hit_probabilities = vec![0.0; pay_table.len()];
+    let mut hit_frequency = 0.0;
+    let mut jackpot_probability = 0.0;
+    // Jackpot spins are kept apart from the rest because the jackpot's average size depends on
+    // how often it's hit, which isn't known until every window has been seen.
+    let mut expected_base_payout = 0.0;
+    let mut expected_base_square = 0.0;
+    let mut expected_base_with_jackpot = 0.0;
+    let mut max_payout_without_jackpot: f64 = 0.0;
+    let mut max_payout_beside_jackpot: f64 = 0.0;
+    let mut bonus_probability = 0.0;
+    let bonus = slot_machine.bonus();
+    let progressive_jackpots = slot_machine.progressive_jackpots();
+    let mut progressive_hit_probabilities = vec![0.0; progressive_jackpots.len()];
+
+    // Lines are scored by the symbols they cross, which repeat far more often than whole windows,
+    // along with the progressive jackpots whose line triggers they show.
+    let mut line_scores: HashMap<u64, LineScore> = HashMap::new();
+    let mut window: Vec<&[Symbol]> = Vec::with_capacity(reel_windows.len());
+    let mut rules_hit = vec![false; pay_table.len()];
+    let mut progressive_hit = vec![false; progressive_jackpots.len()];
+    for_each_window(&reel_windows, &mut window, 1.0, &mut |window, probability| {
+        rules_hit.iter_mut().for_each(|hit| *hit = false);
+        progressive_hit.iter_mut().for_each(|hit| *hit = false);
+        let mut base_payout = 0.0;
+        let mut is_jackpot = false;
+
+        for payline in &layout.paylines {
+            let key = payline
+                .iter()
+                .zip(window)
+                .fold(0u64, |key, (&row, reel)| key * symbol_count + reel[row].0 as u64);
+            let score = line_scores.entry(key).or_insert_with(|| {
+                let line: Vec<Symbol> = payline.iter().zip(window).map(|(&row, reel)| reel[row]).collect();
+                let progressive_triggers = progressive_jackpots
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, progressive)| line_shows_pattern(&line, &progressive.trigger, symbols))
+                    .map(|(index, _)| index)
+                    .collect();
+                LineScore {
+                    rules: matching_rules(&line, pay_table, symbols, slot_machine.evaluation()),
+                    progressive_triggers,
+                }
+            });
+            for &index in &score.progressive_triggers {
+                progressive_hit[index] = true;
+            }
+            for &(index, multiplier) in &score.rules {
+                rules_hit[index] = true;
+                if pay_table[index].is_jackpot {
+                    is_jackpot = true;
+                } else {
+                    base_payout += pay_table[index].payout.saturating_mul(multiplier) as f64;
+                }
+            }
+        }
+        for index in scatter_rules(window, pay_table) {
+            rules_hit[index] = true;
+            if pay_table[index].is_jackpot {
+                is_jackpot = true;
+            } else {
+                base_payout += pay_table[index].payout as f64;
+            }
+        }
+
+        if bonus.is_some_and(|bonus| count_in_window(window, bonus.symbol) >= bonus.count as usize) {
+            bonus_probability += probability;
+        }
+        for (index, progressive) in progressive_jackpots.iter().enumerate() {
+            if let PayPattern::Scatter(symbol, min_count) = progressive.trigger {
+                progressive_hit[index] = count_in_window(window, symbol) >= min_count as usize;
+            }
+            if progressive_hit[index] {
+                progressive_hit_probabilities[index] += probability;
+            }
+        }
+
+        for (index, hit) in rules_hit.iter().enumerate() {
+            if *hit {
+                rule_hit_probabilities[index] += probability;
+            }
+        }
+        if base_payout > 0.0 || is_jackpot {
+            hit_frequency += probability;
+        }
+        expected_base_payout += base_payout * probability;
+        expected_base_square += base_payout * base_payout * probability;
+        if is_jackpot {
+            jackpot_probability += probability;
+            expected_base_with_jackpot += base_payout * probability;
+            max_payout_beside_jackpot = max_payout_beside_jackpot.max(base_payout);
+        } else {
+            max_payout_without_jackpot = max_payout_without_jackpot.max(base_payout);
+        }
+    });
+
+    // Each bonus round is worth `free_spins` spins, and each of those can trigger another round.
+    let (free_spins_per_spin, win_multiplier) = match bonus {
+        Some(bonus) => {
+            let free_spins_per_round = bonus_probability * bonus.free_spins as f64;
+            let free_spins_per_spin = if free_spins_per_round < 1.0 {
+                free_spins_per_round / (1.0 - free_spins_per_round)
+            } else {
+                f64::INFINITY
+            };
+            (free_spins_per_spin, bonus.multiplier as f64)
+        }
+        None => (0.0, 1.0),
+    };
+
+    // The jackpot grows on every paid spin that misses it, so on average it has grown for
+    // (1 - p) / p spins by the time somebody hits it.
+    let jackpot_rate = (jackpot_probability * (1.0 + free_spins_per_spin)).min(1.0);
+    let growth_per_spin = slot_machine.cost_per_play as f64 * slot_machine.jackpot_growth_rate();
+    let expected_jackpot = if jackpot_rate > 0.0 {
+        slot_machine.min_jackpot() as f64 + growth_per_spin * (1.0 - jackpot_rate) / jackpot_rate
+    } else {
+        slot_machine.min_jackpot() as f64
+    };
+
+    let expected_payout = expected_base_payout + expected_jackpot * jackpot_probability;
+    let expected_free_spin_payout = expected_base_payout * win_multiplier + expected_jackpot * jackpot_probability;
+    let expected_square = expected_base_square
+        + 2.0 * expected_jackpot * expected_base_with_jackpot
+        + expected_jackpot * expected_jackpot * jackpot_probability;
+    let cost = slot_machine.cost_per_play as f64;
+    // A pool that can't be won here never pays back what this machine puts into it.
+    let expected_progressive_payout: f64 = progressive_jackpots
+        .iter()
+        .zip(&progressive_hit_probabilities)
+        .filter(|(_, probability)| **probability > 0.0)
+        .map(|(progressive, probability)| {
+            cost * progressive.contribution_rate + probability * (1.0 + free_spins_per_spin) * progressive.pool.seed as f64
+        })
+        .sum();
+
+    PayTableAnalysis {
+        rtp: (expected_payout + free_spins_per_spin * expected_free_spin_payout + expected_progressive_payout) / cost,
+        hit_frequency,
+        volatility: (expected_square - expected_payout * expected_payout).max(0.0).sqrt() / cost,
+        jackpot_cycle_length: if jackpot_rate > 0.0 { 1.0 / jackpot_rate } else { f64::INFINITY },
+        rule_hit_probabilities,
+        jackpot_probability,
+        expected_jackpot,
+        max_payout_without_jackpot,
+        max_payout_beside_jackpot,
+        bonus_probability,
+        free_spins_per_spin,
+        progressive_hit_probabilities,
+    }
+}
+
+/// Every window a strip can show, with the chance of stopping on it. Stops that show the same
+/// symbols are merged.
+fn distinct_windows(strip: &[Symbol], rows: usize) -> Vec<(Vec<Symbol>, f64)> {
+    let mut windows: Vec<(Vec<Symbol>, f64)> = Vec::new();
+    let stop_probability = 1.0 / strip.len() as f64;
+    for stop in 0..strip.len() {
+        let visible = visible_symbols(strip, stop, rows);
+        match windows.iter_mut().find(|(window, _)| *window == visible) {
+            Some((_, probability)) => *probability += stop_probability,
+            None => windows.push((visible, stop_probability)),
+        }
+    }
+    windows
+}
+
+/// Calls `visit` with every combination of reel windows from `window.len()` on and its probability.
+fn for_each_window<'a>(
+    reel_windows: &'a [Vec<(Vec<Symbol>, f64)>],
+    window: &mut Vec<&'a [Symbol]>,
+    probability: f64,
+    visit: &mut impl FnMut(&[&'a [Symbol]], f64),
+) {
+    let Some(choices) = reel_windows.get(window.len()) else {
+        visit(window, probability);
+        return;
+    };
+
+    for (symbols, choice_probability) in choices {
+        window.push(symbols);
+        for_each_window(reel_windows, window, probability * choice_probability, visit);
+        window.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::slot_machine::slot_machine::{EvaluationMode, PayRule, ReelLayout, SymbolDefinition, SymbolRole};
+
+    const A: Symbol = Symbol(0);
+    const B: Symbol = Symbol(1);
+
+    /// Three reels of `a b` on one line, so each of the eight windows comes up 1 in 8: `b b b`
+    /// hits the 100 jackpot, `a a a` pays 4 and `a a` from the left pays 1.
+    fn coin_flip_machine() -> SlotMachine {
+        let standard = |emoji: &str| SymbolDefinition {
+            emoji: emoji.to_string(),
+            role: SymbolRole::Standard,
+            multiplier: 1,
+        };
+        SlotMachine::new(
+            "coin flip".to_string(),
+            20,
+            vec![
+                PayRule {
+                    pattern: PayPattern::AllOfAKind(B),
+                    payout: 100,
+                    is_jackpot: true,
+                },
+                PayRule {
+                    pattern: PayPattern::AllOfAKind(A),
+                    payout: 4,
+                    is_jackpot: false,
+                },
+                PayRule {
+                    pattern: PayPattern::MinCount(A, 2),
+                    payout: 1,
+                    is_jackpot: false,
+                },
+            ],
+            0.0,
+            ReelLayout {
+                reels: vec![vec![A, B]; 3],
+                rows: 1,
+                paylines: vec![vec![0, 0, 0]],
+            },
+            vec![standard("a"), standard("b")],
+            0.0,
+        )
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn figures_match_the_hand_count() {
+        let analysis = coin_flip_machine().analysis().clone();
+        // (100 + 4 + 1) / 8 back on a 20 Libcoin spin.
+        assert_close(analysis.rtp, 105.0 / 8.0 / 20.0);
+        assert_close(analysis.hit_frequency, 3.0 / 8.0);
+        assert_eq!(analysis.rule_hit_probabilities, vec![0.125, 0.125, 0.125]);
+        assert_close(analysis.jackpot_probability, 0.125);
+        assert_close(analysis.jackpot_cycle_length, 8.0);
+        assert_close(analysis.expected_jackpot, 100.0);
+        assert_close(analysis.max_payout_without_jackpot, 4.0);
+        assert_close(analysis.max_payout_beside_jackpot, 0.0);
+        let variance = (100.0 * 100.0 + 4.0 * 4.0 + 1.0) / 8.0 - (105.0f64 / 8.0).powi(2);
+        assert_close(analysis.volatility, variance.sqrt() / 20.0);
+    }
+
+    #[test]
+    fn all_matches_adds_up_every_rule() {
+        let analysis = coin_flip_machine().with_evaluation(EvaluationMode::AllMatches).analysis().clone();
+        // `a a a` now pays its 4 and the 1 for `a a` as well.
+        assert_close(analysis.rtp, (100.0 + 5.0 + 1.0) / 8.0 / 20.0);
+        assert_close(analysis.hit_frequency, 3.0 / 8.0);
+        assert_eq!(analysis.rule_hit_probabilities, vec![0.125, 0.125, 0.25]);
+        assert_close(analysis.max_payout_without_jackpot, 5.0);
+    }
+
+    #[test]
+    fn a_growing_jackpot_is_counted_at_its_average() {
+        let machine = coin_flip_machine();
+        // Growing 1 Libcoin a spin, it's been missed 7 times on average when it's hit.
+        let growing = SlotMachine::new(
+            "growing".to_string(),
+            20,
+            machine.pay_table().to_vec(),
+            0.05,
+            machine.layout().clone(),
+            machine.symbols().to_vec(),
+            0.0,
+        );
+        let analysis = analyze_pay_table(&growing);
+        assert_close(analysis.expected_jackpot, 107.0);
+        assert_close(analysis.rtp, (107.0 + 4.0 + 1.0) / 8.0 / 20.0);
+    }
+
+    #[test]
+    fn identical_stops_are_merged() {
+        // The last stop wraps round to show `a a` again.
+        let windows = distinct_windows(&[A, A, B, A], 2);
+        assert_eq!(windows, vec![(vec![A, A], 0.5), (vec![A, B], 0.25), (vec![B, A], 0.25)]);
+        assert_eq!(window_combinations(&[vec![A, A, B, A], vec![A, B]], 2), 6);
+    }
+}
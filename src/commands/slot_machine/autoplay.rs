@@ -0,0 +1,219 @@
+use super::slots::{autocomplete_machine, play_batch, SpinBatch, MAX_SPINS_PER_COMMAND};
+use super::SpinKind;
+use crate::commands::buttons::wait_for_press;
+use crate::commands::hall_of_fame::announce_jackpot;
+use crate::services::free_spins::machine_with_free_spins;
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use serenity::builder::{CreateActionRow, CreateButton, CreateEmbed};
+use std::time::Duration;
+use tracing::error;
+
+const MAX_AUTOPLAY_SPINS: u32 = 100;
+// Each batch is one wager, so the bank is asked to move money twice per batch rather than per spin.
+const AUTOPLAY_BATCH_SPINS: u32 = MAX_SPINS_PER_COMMAND;
+// How long the Stop button is listened for between batches, which also keeps edits well under
+// Discord's rate limit.
+const AUTOPLAY_BATCH_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Running totals for an autoplay session.
+#[derive(Default)]
+struct AutoplayProgress {
+    spins_played: u32,
+    free_spins_played: u32,
+    staked: f64,
+    won: f64,
+    biggest_win: u64,
+    last_spin: Option<String>,
+    jackpots: u32,
+}
+
+impl AutoplayProgress {
+    fn net(&self) -> f64 {
+        self.won - self.staked
+    }
+
+    fn add(&mut self, batch: &SpinBatch) {
+        self.spins_played += batch.spins.len() as u32;
+        if batch.kind == SpinKind::Free {
+            self.free_spins_played += batch.spins.len() as u32;
+        }
+        self.staked += batch.stake;
+        self.won += batch.payout as f64;
+        self.biggest_win = batch.spins.iter().map(|report| report.payout).fold(self.biggest_win, u64::max);
+        self.jackpots += batch.jackpot_hits.len() as u32;
+        self.last_spin = batch.spins.last().map(|report| {
+            report
+                .play_result
+                .window
+                .iter()
+                .map(|row| row.join(""))
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+    }
+}
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Let a slot machine spin on its own until a spin count, loss limit or win target is reached."),
+    description_localized("fr", "Laissez une machine à sous tourner seule jusqu'à un nombre de tours, une perte ou un gain donné."),
+    description_localized("es-ES", "Deja que una tragaperras gire sola hasta un número de tiradas, un límite de pérdidas o un objetivo.")
+)]
+pub async fn autoplay(
+    ctx: Context<'_>,
+    #[description = "How many spins to play"]
+    #[description_localized("fr", "Combien de tours jouer")]
+    #[description_localized("es-ES", "Cuántas tiradas jugar")]
+    #[min = 1]
+    #[max = 100]
+    spins: u32,
+    #[description = "Which slot machine to play"]
+    #[description_localized("fr", "La machine à sous à utiliser")]
+    #[description_localized("es-ES", "La tragaperras que quieres jugar")]
+    #[autocomplete = "autocomplete_machine"]
+    machine: Option<String>,
+    #[description = "Multiple of the machine's cost to stake on each spin; wins scale to match"]
+    #[description_localized("fr", "Multiple du prix de la machine misé sur chaque tour; les gains suivent")]
+    #[description_localized("es-ES", "Múltiplo del precio de la máquina apostado en cada tirada; los premios también")]
+    #[min = 1]
+    bet: Option<u32>,
+    #[description = "Stop once you've lost this much Libcoin"]
+    #[description_localized("fr", "S'arrêter après avoir perdu autant de Libcoin")]
+    #[description_localized("es-ES", "Parar al perder esta cantidad de Libcoin")]
+    #[min = 1]
+    loss_limit: Option<u32>,
+    #[description = "Stop once you're this much Libcoin up"]
+    #[description_localized("fr", "S'arrêter après avoir gagné autant de Libcoin")]
+    #[description_localized("es-ES", "Parar al ir ganando esta cantidad de Libcoin")]
+    #[min = 1]
+    win_target: Option<u32>,
+) -> Result<(), Error> {
+    if spins == 0 || spins > MAX_AUTOPLAY_SPINS {
+        return Err(Error::from(format!("Autoplay runs from 1 to {} spins.", MAX_AUTOPLAY_SPINS)));
+    }
+    let bet = bet.unwrap_or(1);
+    // Pinned before the first batch so every batch, and the loss limit's spin cost, is on the same
+    // machine. Without one asked for, that's the one a bonus round is still in progress on.
+    let machine = match machine {
+        Some(machine) => Some(machine),
+        None => machine_with_free_spins(ctx.author().id.get()).unwrap_or_else(|reason| {
+            error!("Couldn't look up free spins for {}: {reason:?}", ctx.author().id);
+            None
+        }),
+    };
+    let (machine_name, spin_cost) = {
+        let slot_machine = ctx.data().slot_machines.get(machine.as_deref())?.lock().unwrap();
+        (slot_machine.name().to_string(), slot_machine.cost_per_play as u64 * bet as u64)
+    };
+
+    let stop_id = format!("{}-autoplay-stop", ctx.id());
+    let stop_button = CreateActionRow::Buttons(vec![CreateButton::new(&stop_id)
+        .style(serenity::ButtonStyle::Danger)
+        .label("Stop")]);
+    let mut progress = AutoplayProgress::default();
+    let reply = ctx
+        .send(CreateReply {
+            embeds: vec![progress_embed(&machine_name, spins, &progress, "Starting up…")],
+            components: Some(vec![stop_button.clone()]),
+            ..Default::default()
+        })
+        .await?;
+
+    let status = loop {
+        if progress.spins_played >= spins {
+            break "Finished all the spins.".to_string();
+        }
+        if let Some(loss_limit) = loss_limit {
+            if -progress.net() >= loss_limit as f64 {
+                break "Stopped at your loss limit.".to_string();
+            }
+        }
+        if let Some(win_target) = win_target {
+            if progress.net() >= win_target as f64 {
+                break "Stopped at your win target. 🎉".to_string();
+            }
+        }
+
+        // A batch never stakes more than is left of the loss limit, so the limit is never overshot.
+        let mut batch_spins = AUTOPLAY_BATCH_SPINS.min(spins - progress.spins_played);
+        if let Some(loss_limit) = loss_limit {
+            let affordable = ((loss_limit as f64 + progress.net()) / spin_cost as f64).floor() as u32;
+            if affordable == 0 {
+                break "Stopped at your loss limit.".to_string();
+            }
+            batch_spins = batch_spins.min(affordable);
+        }
+
+        let batch = match play_batch(ctx, Some(machine_name.clone()), bet, batch_spins).await {
+            Ok(batch) => batch,
+            Err(reason) => break format!("Stopped: {}", reason),
+        };
+        progress.add(&batch);
+        for hit in &batch.jackpot_hits {
+            announce_jackpot(ctx, hit).await;
+        }
+
+        let running = format!("Spinning… {} of {} spins played.", progress.spins_played, spins);
+        if let Err(reason) = reply
+            .edit(
+                ctx,
+                CreateReply {
+                    embeds: vec![progress_embed(&machine_name, spins, &progress, &running)],
+                    components: Some(vec![stop_button.clone()]),
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            error!("Couldn't update autoplay progress: {reason:?}");
+        }
+
+        let stopped = progress.spins_played < spins
+            && wait_for_press(ctx, std::slice::from_ref(&stop_id), AUTOPLAY_BATCH_INTERVAL)
+                .await
+                .is_some();
+        if stopped {
+            break "Stopped at your request.".to_string();
+        }
+    };
+
+    reply
+        .edit(
+            ctx,
+            CreateReply {
+                embeds: vec![progress_embed(&machine_name, spins, &progress, &status)],
+                components: Some(Vec::new()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn progress_embed(machine_name: &str, spins: u32, progress: &AutoplayProgress, status: &str) -> CreateEmbed {
+    let spins_played = if progress.free_spins_played > 0 {
+        format!("{} / {} ({} free)", progress.spins_played, spins, progress.free_spins_played)
+    } else {
+        format!("{} / {}", progress.spins_played, spins)
+    };
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(format!("🤖 Autoplay on {}", machine_name))
+        .description(status)
+        .fields([
+            ("Spins", spins_played, true),
+            ("Staked", format!("{:.0}", progress.staked), true),
+            ("Won", format!("{:.0}", progress.won), true),
+            ("Net Gain/Loss", format!("{:+.0}", progress.net()), true),
+            ("Biggest Win", progress.biggest_win.to_string(), true),
+            ("Jackpots", progress.jackpots.to_string(), true),
+        ]);
+
+    match &progress.last_spin {
+        Some(last_spin) => embed.field("Last Spin", last_spin, false),
+        None => embed,
+    }
+}
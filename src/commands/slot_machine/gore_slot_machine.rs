@@ -1,77 +1,8 @@
-use super::slot_machine::*;
-use std::collections::HashMap;
+use super::machine_definition::{parse_toml_machine_definition, MachineDefinition};
 
-fn gore_slots_paytable() -> Vec<PayRule> {
-    vec![
-        PayRule {
-            pattern: PayPattern::FiveOfAKind(Symbol::Gore),
-            payout: 500,
-            is_jackpot: true,
-        },
-        PayRule {
-            pattern: PayPattern::FiveOfAKind(Symbol::Mean),
-            payout: 250,
-            is_jackpot: false,
-        },
-        PayRule {
-            pattern: PayPattern::FiveOfAKind(Symbol::Magnathonk),
-            payout: 250,
-            is_jackpot: false,
-        },
-        PayRule {
-            pattern: PayPattern::ThreeOfAKind(Symbol::Smugbrow),
-            payout: 25,
-            is_jackpot: false,
-        },
-        PayRule {
-            pattern: PayPattern::ThreeOfAKind(Symbol::Smileyes),
-            payout: 25,
-            is_jackpot: false,
-        },
-        PayRule {
-            pattern: PayPattern::MinCount(Symbol::Smileyes, 2),
-            payout: 6,
-            is_jackpot: false,
-        },
-        PayRule {
-            pattern: PayPattern::MinCountAnyDistribution(
-                vec![Symbol::Smugbrow, Symbol::Magnathonk, Symbol::Mean],
-                3,
-            ),
-            payout: 6,
-            is_jackpot: false,
-        },
-        PayRule {
-            pattern: PayPattern::MinCount(Symbol::Smileyes, 1),
-            payout: 2,
-            is_jackpot: false,
-        },
-    ]
-}
-
-pub fn generate_gore_slots(previous_rolling_jackpot: f64) -> SlotMachine {
-    let weighted_symbol_pool: Vec<Symbol> = generate_gore_slots_weights();
-    let pay_table: Vec<PayRule> = gore_slots_paytable();
-    let cost_per_play: u32 = 10; // Cost per play in cents
-    let jackpot_growth_rate: f64 = 0.01; // 5% growth rate for the jackpot
-    let slot_machine = SlotMachine::new(
-        cost_per_play,
-        pay_table,
-        jackpot_growth_rate,
-        weighted_symbol_pool,
-        previous_rolling_jackpot,
-    );
-    return slot_machine;
-}
-
-fn generate_gore_slots_weights() -> Vec<Symbol> {
-    let mut weights = HashMap::new();
-    weights.insert(Symbol::Gore, 9 as f64);
-    weights.insert(Symbol::Mean, 10 as f64);
-    weights.insert(Symbol::Magnathonk, 12 as f64);
-    weights.insert(Symbol::Smugbrow, 19 as f64);
-    weights.insert(Symbol::Smileyes, 20 as f64);
-    weights.insert(Symbol::Blank, 6 as f64);
+// Compiled in so the bot always has a machine to offer, even without a definitions directory.
+const GORE_SLOTS_DEFINITION: &str = include_str!("../../../machines/gore.toml");
 
-    return generate_weighted_symbol_pool(weights.clone());
+pub fn gore_slots_definition() -> MachineDefinition {
+    parse_toml_machine_definition(GORE_SLOTS_DEFINITION).expect("Built-in gore slot machine definition is invalid")
 }
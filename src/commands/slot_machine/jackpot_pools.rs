@@ -0,0 +1,75 @@
+use crate::Error;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+// Compiled in so machines always have the standard pools to feed, even without a pools file.
+const DEFAULT_JACKPOT_POOLS: &str = include_str!("../../../jackpot_pools.toml");
+
+/// A progressive jackpot shared between machines. Its value lives in the database so every
+/// machine, and every guild, plays for the same pot.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JackpotPool {
+    pub name: String,
+    /// What the pool starts from, and goes back to each time it's won.
+    pub seed: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JackpotPoolsDefinition {
+    pools: Vec<JackpotPool>,
+}
+
+impl JackpotPool {
+    /// "grand" becomes "Grand Jackpot".
+    pub fn title(&self) -> String {
+        let mut characters = self.name.chars();
+        match characters.next() {
+            Some(first) => format!("{}{} Jackpot", first.to_uppercase(), characters.as_str()),
+            None => "Jackpot".to_string(),
+        }
+    }
+}
+
+/// The pools in `path`, in the order they're listed, or the built-in mini/major/grand tiers if
+/// there's no such file.
+pub fn load_jackpot_pools(path: &Path) -> Result<Vec<JackpotPool>, Error> {
+    if !path.is_file() {
+        return default_jackpot_pools();
+    }
+
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::from(format!("Couldn't read jackpot pools {}: {}", path.display(), e)))?;
+    parse_jackpot_pools(&contents)
+        .map_err(|e| Error::from(format!("Couldn't load jackpot pools {}: {}", path.display(), e)))
+}
+
+pub fn default_jackpot_pools() -> Result<Vec<JackpotPool>, Error> {
+    parse_jackpot_pools(DEFAULT_JACKPOT_POOLS)
+}
+
+fn parse_jackpot_pools(contents: &str) -> Result<Vec<JackpotPool>, Error> {
+    let definition: JackpotPoolsDefinition = toml::from_str(contents)?;
+
+    let mut problems: Vec<String> = Vec::new();
+    let mut names = HashSet::new();
+    for (index, pool) in definition.pools.iter().enumerate() {
+        if pool.name.trim().is_empty() {
+            problems.push(format!("pools[{}]: name must not be empty", index));
+        }
+        if !names.insert(pool.name.as_str()) {
+            problems.push(format!("pools[{}]: duplicate pool name '{}'", index, pool.name));
+        }
+        if pool.seed == 0 {
+            problems.push(format!("pools[{}] ('{}'): seed must be greater than 0", index, pool.name));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(Error::from(format!("Invalid jackpot pools:\n - {}", problems.join("\n - "))));
+    }
+
+    Ok(definition.pools)
+}
@@ -0,0 +1,624 @@
+use super::analysis::window_combinations;
+use super::jackpot_pools::JackpotPool;
+use super::slot_machine::*;
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+const MAX_REEL_COUNT: usize = 10;
+const MAX_ROWS: usize = 5;
+/// Paytables are analysed by visiting every combination of reel windows, so this keeps `/paytable` quick.
+const MAX_WINDOW_COMBINATIONS: u128 = 5_000_000;
+
+/// A slot machine as described in a `.toml` or `.json` file. Symbols are referred to by name in
+/// pay rules and reel strips and resolved to `Symbol` indices when the machine is built.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachineDefinition {
+    pub name: String,
+    pub cost_per_play: u32,
+    /// The largest multiple of `cost_per_play` a player can bet on one spin.
+    #[serde(default = "default_max_bet")]
+    pub max_bet: u32,
+    pub jackpot_growth_rate: f64,
+    #[serde(default = "default_reel_count")]
+    pub reel_count: usize,
+    #[serde(default = "default_rows")]
+    pub rows: usize,
+    pub symbols: Vec<SymbolConfig>,
+    /// Symbol names on each reel's strip, top to bottom. When left out, every reel gets a strip
+    /// laid out from the symbol weights.
+    #[serde(default)]
+    pub reels: Option<Vec<Vec<String>>>,
+    /// The row each payline crosses on each reel, 0 being the top. Defaults to every horizontal
+    /// row, middle first.
+    #[serde(default)]
+    pub paylines: Option<Vec<Vec<usize>>>,
+    pub pay_rules: Vec<PayRuleConfig>,
+    #[serde(default)]
+    pub evaluation: EvaluationModeConfig,
+    #[serde(default)]
+    pub bonus: Option<BonusConfig>,
+    /// Shared pools this machine feeds and can pay out; see `jackpot_pools.toml`.
+    #[serde(default)]
+    pub progressive_jackpots: Vec<ProgressiveJackpotConfig>,
+}
+
+/// Feeds `contribution_rate` of each paid spin into `pool`, which is won whenever the window
+/// shows `trigger`. Scatter triggers count their symbol anywhere, whatever its role.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProgressiveJackpotConfig {
+    pub pool: String,
+    pub contribution_rate: f64,
+    pub trigger: PayPatternConfig,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationModeConfig {
+    #[default]
+    FirstMatch,
+    AllMatches,
+    BestMatch,
+}
+
+/// Free spins for landing `count` or more of `symbol` anywhere in the window.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BonusConfig {
+    pub symbol: String,
+    pub count: u8,
+    pub free_spins: u32,
+    #[serde(default = "default_bonus_multiplier")]
+    pub multiplier: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SymbolConfig {
+    pub name: String,
+    pub emoji: String,
+    /// How often the symbol appears on generated strips. Only used when `reels` is left out.
+    #[serde(default)]
+    pub weight: Option<u32>,
+    #[serde(default)]
+    pub role: SymbolRoleConfig,
+    /// Multiplies line wins the symbol is part of.
+    #[serde(default)]
+    pub multiplier: Option<u32>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolRoleConfig {
+    #[default]
+    Standard,
+    Wild,
+    Scatter,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PayRuleConfig {
+    pub pattern: PayPatternConfig,
+    pub payout: u32,
+    #[serde(default)]
+    pub is_jackpot: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PayPatternConfig {
+    #[serde(alias = "five_of_a_kind")]
+    AllOfAKind { symbol: String },
+    ThreeOfAKind { symbol: String },
+    MinCountAnyDistribution { symbols: Vec<String>, count: u8 },
+    MinCount { symbol: String, count: u8 },
+    Scatter { symbol: String, count: u8 },
+}
+
+fn default_max_bet() -> u32 {
+    1
+}
+
+fn default_reel_count() -> usize {
+    5
+}
+
+fn default_rows() -> usize {
+    3
+}
+
+fn default_bonus_multiplier() -> u32 {
+    1
+}
+
+fn default_paylines(reel_count: usize, rows: usize) -> Vec<Vec<usize>> {
+    let middle = rows / 2;
+    std::iter::once(middle)
+        .chain((0..rows).filter(|&row| row != middle))
+        .map(|row| vec![row; reel_count])
+        .collect()
+}
+
+fn resolve_pattern(
+    pattern: &PayPatternConfig,
+    symbol_indices: &HashMap<&str, Symbol>,
+    context: &str,
+) -> Result<PayPattern, String> {
+    let resolve = |name: &str| {
+        symbol_indices
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("{}: unknown symbol '{}'", context, name))
+    };
+
+    match pattern {
+        PayPatternConfig::AllOfAKind { symbol } => resolve(symbol).map(PayPattern::AllOfAKind),
+        PayPatternConfig::ThreeOfAKind { symbol } => resolve(symbol).map(PayPattern::ThreeOfAKind),
+        PayPatternConfig::MinCount { symbol, count } => resolve(symbol).map(|symbol| PayPattern::MinCount(symbol, *count)),
+        PayPatternConfig::MinCountAnyDistribution { symbols, .. } if symbols.is_empty() => {
+            Err(format!("{}: symbols must not be empty", context))
+        }
+        PayPatternConfig::MinCountAnyDistribution { symbols, count } => symbols
+            .iter()
+            .map(|name| resolve(name))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|symbols| PayPattern::MinCountAnyDistribution(symbols, *count)),
+        PayPatternConfig::Scatter { symbol, count } => resolve(symbol).map(|symbol| PayPattern::Scatter(symbol, *count)),
+    }
+}
+
+/// Flags patterns asking for more symbols than a line, or for scatters the window, can show.
+fn check_pattern_count(
+    pattern: &PayPatternConfig,
+    reel_count: usize,
+    rows: usize,
+    context: &str,
+    problems: &mut Vec<String>,
+) {
+    let required_count = match pattern {
+        PayPatternConfig::AllOfAKind { .. } => 1,
+        PayPatternConfig::ThreeOfAKind { .. } => 3,
+        PayPatternConfig::MinCount { count, .. }
+        | PayPatternConfig::MinCountAnyDistribution { count, .. }
+        | PayPatternConfig::Scatter { count, .. } => *count as usize,
+    };
+    if let PayPatternConfig::Scatter { count, .. } = pattern {
+        let window_size = reel_count * rows;
+        if *count == 0 || *count as usize > window_size {
+            problems.push(format!(
+                "{}: needs {} scatters but the window only shows {} symbols",
+                context, count, window_size
+            ));
+        }
+    } else if required_count == 0 || required_count > reel_count {
+        problems.push(format!(
+            "{}: needs {} matching symbols but the machine only has {} reels",
+            context, required_count, reel_count
+        ));
+    }
+}
+
+pub fn load_machine_definition(path: &Path) -> Result<MachineDefinition, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::from(format!("Couldn't read slot machine definition {}: {}", path.display(), e)))?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => parse_toml_machine_definition(&contents),
+        Some("json") => serde_json::from_str(&contents).map_err(Error::from),
+        _ => Err(Error::from("Slot machine definitions must be .toml or .json files")),
+    }
+    .map_err(|e| Error::from(format!("Couldn't parse slot machine definition {}: {}", path.display(), e)))
+}
+
+pub fn parse_toml_machine_definition(contents: &str) -> Result<MachineDefinition, Error> {
+    toml::from_str(contents).map_err(Error::from)
+}
+
+impl MachineDefinition {
+    /// SHA-256 of the definition and `ENGINE_VERSION`. Spins recorded under another hash were played
+    /// by a different machine or engine, so replaying them now proves nothing either way.
+    pub fn definition_hash(&self) -> Result<String, Error> {
+        let definition = serde_json::to_string(self)?;
+        Ok(format!("{:x}", Sha256::digest(format!("{}:{}", ENGINE_VERSION, definition).as_bytes())))
+    }
+
+    /// Checks the definition and turns it into a playable machine, reporting every problem at once.
+    /// Progressive jackpots may only name pools from `pools`.
+    pub fn build(self, previous_rolling_jackpot: f64, pools: &[JackpotPool]) -> Result<SlotMachine, Error> {
+        let definition_hash = self.definition_hash()?;
+        let mut problems: Vec<String> = Vec::new();
+
+        if self.name.trim().is_empty() {
+            problems.push("name must not be empty".to_string());
+        }
+        if self.cost_per_play == 0 {
+            problems.push("cost_per_play must be greater than 0".to_string());
+        }
+        if self.max_bet == 0 {
+            problems.push("max_bet must be at least 1".to_string());
+        }
+        if !self.jackpot_growth_rate.is_finite() || self.jackpot_growth_rate < 0.0 {
+            problems.push("jackpot_growth_rate must be a non-negative number".to_string());
+        }
+        if self.reel_count == 0 || self.reel_count > MAX_REEL_COUNT {
+            problems.push(format!("reel_count must be between 1 and {}", MAX_REEL_COUNT));
+        }
+        if self.rows == 0 || self.rows > MAX_ROWS {
+            problems.push(format!("rows must be between 1 and {}", MAX_ROWS));
+        }
+        if self.symbols.is_empty() {
+            problems.push("at least one symbol is required".to_string());
+        }
+
+        let mut symbol_indices: HashMap<&str, Symbol> = HashMap::new();
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            if symbol_indices.insert(symbol.name.as_str(), Symbol(index)).is_some() {
+                problems.push(format!("symbols[{}]: duplicate symbol name '{}'", index, symbol.name));
+            }
+            match (symbol.weight, &self.reels) {
+                (Some(_), Some(_)) => problems.push(format!(
+                    "symbols[{}] ('{}'): weight has no effect when reels are listed",
+                    index, symbol.name
+                )),
+                (None, None) => problems.push(format!(
+                    "symbols[{}] ('{}'): weight is required when reels aren't listed",
+                    index, symbol.name
+                )),
+                (Some(0), None) => {
+                    problems.push(format!("symbols[{}] ('{}'): weight must be greater than 0", index, symbol.name))
+                }
+                _ => {}
+            }
+            if symbol.emoji.trim().is_empty() {
+                problems.push(format!("symbols[{}] ('{}'): emoji must not be empty", index, symbol.name));
+            }
+            match (symbol.multiplier, symbol.role) {
+                (Some(multiplier), _) if multiplier < 2 => problems.push(format!(
+                    "symbols[{}] ('{}'): multiplier must be at least 2",
+                    index, symbol.name
+                )),
+                (Some(_), SymbolRoleConfig::Scatter) => problems.push(format!(
+                    "symbols[{}] ('{}'): scatters aren't part of line wins, so they can't have a multiplier",
+                    index, symbol.name
+                )),
+                _ => {}
+            }
+        }
+
+        let reels: Vec<Vec<Symbol>> = match &self.reels {
+            Some(reels) => {
+                if reels.len() != self.reel_count {
+                    problems.push(format!(
+                        "reels lists {} strips but reel_count is {}",
+                        reels.len(),
+                        self.reel_count
+                    ));
+                }
+                let mut strips = Vec::new();
+                for (index, strip) in reels.iter().enumerate() {
+                    if strip.len() < self.rows {
+                        problems.push(format!(
+                            "reels[{}]: needs at least {} symbols to fill the window",
+                            index, self.rows
+                        ));
+                    }
+                    let mut resolved = Vec::new();
+                    for name in strip {
+                        match symbol_indices.get(name.as_str()) {
+                            Some(symbol) => resolved.push(*symbol),
+                            None => problems.push(format!("reels[{}]: unknown symbol '{}'", index, name)),
+                        }
+                    }
+                    strips.push(resolved);
+                }
+                strips
+            }
+            None => {
+                let weights: Vec<u32> = self.symbols.iter().map(|symbol| symbol.weight.unwrap_or(0)).collect();
+                vec![generate_reel_strip(&weights); self.reel_count]
+            }
+        };
+
+        let paylines = self
+            .paylines
+            .clone()
+            .unwrap_or_else(|| default_paylines(self.reel_count, self.rows));
+        if paylines.is_empty() {
+            problems.push("at least one payline is required".to_string());
+        }
+        for (index, payline) in paylines.iter().enumerate() {
+            if payline.len() != self.reel_count {
+                problems.push(format!(
+                    "paylines[{}]: crosses {} reels but the machine has {}",
+                    index,
+                    payline.len(),
+                    self.reel_count
+                ));
+            }
+            if payline.iter().any(|&row| row >= self.rows) {
+                problems.push(format!(
+                    "paylines[{}]: rows must be between 0 and {}",
+                    index,
+                    self.rows.saturating_sub(1)
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            let combinations = window_combinations(&reels, self.rows);
+            if combinations > MAX_WINDOW_COMBINATIONS {
+                problems.push(format!(
+                    "the reels can stop in {} different ways, more than the {} that can be analysed; use shorter strips",
+                    combinations, MAX_WINDOW_COMBINATIONS
+                ));
+            }
+        }
+
+        if self.pay_rules.is_empty() {
+            problems.push("at least one pay rule is required".to_string());
+        }
+        if !self.pay_rules.iter().any(|rule| rule.is_jackpot) {
+            problems.push("at least one pay rule must have is_jackpot = true".to_string());
+        }
+
+        let mut pay_table: Vec<PayRule> = Vec::new();
+        for (index, rule) in self.pay_rules.iter().enumerate() {
+            let context = format!("pay_rules[{}]", index);
+            let pattern = resolve_pattern(&rule.pattern, &symbol_indices, &context);
+
+            // Scatters only pay through scatter rules, and scatter rules only count scatters.
+            let is_scatter = |symbol: &Symbol| matches!(self.symbols[symbol.0].role, SymbolRoleConfig::Scatter);
+            match &pattern {
+                Ok(PayPattern::Scatter(symbol, _)) if !is_scatter(symbol) => problems.push(format!(
+                    "pay_rules[{}]: '{}' isn't a scatter symbol",
+                    index, self.symbols[symbol.0].name
+                )),
+                Ok(PayPattern::AllOfAKind(symbol))
+                | Ok(PayPattern::ThreeOfAKind(symbol))
+                | Ok(PayPattern::MinCount(symbol, _))
+                    if is_scatter(symbol) =>
+                {
+                    problems.push(format!(
+                        "pay_rules[{}]: scatter symbol '{}' can only be paid by a scatter rule",
+                        index, self.symbols[symbol.0].name
+                    ))
+                }
+                Ok(PayPattern::MinCountAnyDistribution(symbols, _)) if symbols.iter().any(is_scatter) => {
+                    problems.push(format!("pay_rules[{}]: scatter symbols can only be paid by a scatter rule", index))
+                }
+                _ => {}
+            }
+
+            check_pattern_count(&rule.pattern, self.reel_count, self.rows, &context, &mut problems);
+
+            match pattern {
+                Ok(pattern) => pay_table.push(PayRule {
+                    pattern,
+                    payout: rule.payout,
+                    is_jackpot: rule.is_jackpot,
+                }),
+                Err(problem) => problems.push(problem),
+            }
+        }
+
+        let bonus = self.bonus.as_ref().and_then(|bonus| {
+            let window_size = self.reel_count * self.rows;
+            if bonus.count == 0 || bonus.count as usize > window_size {
+                problems.push(format!(
+                    "bonus: needs {} symbols but the window only shows {}",
+                    bonus.count, window_size
+                ));
+            }
+            if bonus.free_spins == 0 {
+                problems.push("bonus: free_spins must be greater than 0".to_string());
+            }
+            if bonus.multiplier == 0 {
+                problems.push("bonus: multiplier must be greater than 0".to_string());
+            }
+            match symbol_indices.get(bonus.symbol.as_str()) {
+                Some(symbol) => Some(BonusRound {
+                    symbol: *symbol,
+                    count: bonus.count,
+                    free_spins: bonus.free_spins,
+                    multiplier: bonus.multiplier,
+                }),
+                None => {
+                    problems.push(format!("bonus: unknown symbol '{}'", bonus.symbol));
+                    None
+                }
+            }
+        });
+
+        let mut progressive_jackpots: Vec<ProgressiveJackpot> = Vec::new();
+        for (index, progressive) in self.progressive_jackpots.iter().enumerate() {
+            let context = format!("progressive_jackpots[{}]", index);
+            if !progressive.contribution_rate.is_finite() || !(0.0..=1.0).contains(&progressive.contribution_rate) {
+                problems.push(format!("{}: contribution_rate must be between 0 and 1", context));
+            }
+            if self.progressive_jackpots[..index].iter().any(|earlier| earlier.pool == progressive.pool) {
+                problems.push(format!("{}: the machine already feeds pool '{}'", context, progressive.pool));
+            }
+            check_pattern_count(&progressive.trigger, self.reel_count, self.rows, &context, &mut problems);
+
+            let pool = pools.iter().find(|pool| pool.name == progressive.pool);
+            match (pool, resolve_pattern(&progressive.trigger, &symbol_indices, &context)) {
+                (Some(pool), Ok(trigger)) => progressive_jackpots.push(ProgressiveJackpot {
+                    pool: pool.clone(),
+                    contribution_rate: progressive.contribution_rate,
+                    trigger,
+                }),
+                (None, trigger) => {
+                    problems.push(format!("{}: unknown jackpot pool '{}'", context, progressive.pool));
+                    if let Err(problem) = trigger {
+                        problems.push(problem);
+                    }
+                }
+                (_, Err(problem)) => problems.push(problem),
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(Error::from(format!(
+                "Invalid slot machine definition '{}':\n - {}",
+                self.name,
+                problems.join("\n - ")
+            )));
+        }
+
+        let symbols = self
+            .symbols
+            .into_iter()
+            .map(|symbol| SymbolDefinition {
+                emoji: symbol.emoji,
+                role: match symbol.role {
+                    SymbolRoleConfig::Standard => SymbolRole::Standard,
+                    SymbolRoleConfig::Wild => SymbolRole::Wild,
+                    SymbolRoleConfig::Scatter => SymbolRole::Scatter,
+                },
+                multiplier: symbol.multiplier.unwrap_or(1),
+            })
+            .collect();
+
+        let slot_machine = SlotMachine::new(
+            self.name,
+            self.cost_per_play,
+            pay_table,
+            self.jackpot_growth_rate,
+            ReelLayout {
+                reels,
+                rows: self.rows,
+                paylines,
+            },
+            symbols,
+            previous_rolling_jackpot,
+        )
+        .with_evaluation(match self.evaluation {
+            EvaluationModeConfig::FirstMatch => EvaluationMode::FirstMatch,
+            EvaluationModeConfig::AllMatches => EvaluationMode::AllMatches,
+            EvaluationModeConfig::BestMatch => EvaluationMode::BestMatch,
+        })
+        .with_progressive_jackpots(progressive_jackpots)
+        .with_max_bet(self.max_bet)
+        .with_definition_hash(definition_hash);
+
+        let Some(bonus) = bonus else {
+            return Ok(slot_machine);
+        };
+        let slot_machine = slot_machine.with_bonus(bonus);
+        if !slot_machine.analysis().free_spins_per_spin.is_finite() {
+            return Err(Error::from(format!(
+                "Invalid slot machine definition '{}':\n - bonus: free spins retrigger so often that a bonus round would never end",
+                slot_machine.name()
+            )));
+        }
+
+        Ok(slot_machine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TINY: &str = r#"
+name = "tiny"
+cost_per_play = 1
+jackpot_growth_rate = 0.0
+reel_count = 3
+rows = 1
+reels = [["a", "b"], ["a", "b"], ["a", "b"]]
+
+[[symbols]]
+name = "a"
+emoji = "🅰️"
+
+[[symbols]]
+name = "b"
+emoji = "🅱️"
+
+[[pay_rules]]
+pattern = { type = "all_of_a_kind", symbol = "a" }
+payout = 10
+is_jackpot = true
+"#;
+
+    fn build(definition: &str) -> Result<SlotMachine, Error> {
+        parse_toml_machine_definition(definition)?.build(0.0, &[])
+    }
+
+    fn problems(definition: &str) -> String {
+        match build(definition) {
+            Ok(_) => panic!("definition should have been rejected"),
+            Err(reason) => reason.to_string(),
+        }
+    }
+
+    #[test]
+    fn a_valid_definition_builds() {
+        let machine = build(TINY).unwrap();
+        assert_eq!(machine.name(), "tiny");
+        assert_eq!(machine.reel_count(), 3);
+        // One row and no paylines listed: the single row is the only line.
+        assert_eq!(machine.layout().paylines, vec![vec![0, 0, 0]]);
+        assert_eq!(machine.min_jackpot(), 10);
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let definition = TINY
+            .replace("cost_per_play = 1", "cost_per_play = 0")
+            .replace("[\"a\", \"b\"], [\"a\", \"b\"]]", "[\"a\", \"c\"], [\"a\", \"b\"]]")
+            .replace("is_jackpot = true", "is_jackpot = false");
+        let problems = problems(&definition);
+        assert!(problems.starts_with("Invalid slot machine definition 'tiny':"));
+        assert!(problems.contains("cost_per_play must be greater than 0"));
+        assert!(problems.contains("reels[1]: unknown symbol 'c'"));
+        assert!(problems.contains("at least one pay rule must have is_jackpot = true"));
+    }
+
+    #[test]
+    fn strips_and_weights_cant_be_mixed() {
+        let weighted = TINY.replace("emoji = \"🅰️\"", "emoji = \"🅰️\"\nweight = 2");
+        assert!(problems(&weighted).contains("symbols[0] ('a'): weight has no effect when reels are listed"));
+
+        let unweighted = TINY.replace("reels = [[\"a\", \"b\"], [\"a\", \"b\"], [\"a\", \"b\"]]\n", "");
+        let problems = problems(&unweighted);
+        assert!(problems.contains("symbols[0] ('a'): weight is required when reels aren't listed"));
+        assert!(problems.contains("symbols[1] ('b'): weight is required when reels aren't listed"));
+    }
+
+    #[test]
+    fn reels_and_paylines_must_fit_the_machine() {
+        let short = TINY.replace("reel_count = 3", "reel_count = 4");
+        assert!(problems(&short).contains("reels lists 3 strips but reel_count is 4"));
+
+        let off_the_window = TINY.replace("rows = 1", "rows = 1\npaylines = [[0, 1, 0]]");
+        assert!(problems(&off_the_window).contains("paylines[0]: rows must be between 0 and 0"));
+
+        let too_many_reels = TINY.replace("reel_count = 3", &format!("reel_count = {}", MAX_REEL_COUNT + 1));
+        assert!(problems(&too_many_reels).contains(&format!("reel_count must be between 1 and {}", MAX_REEL_COUNT)));
+    }
+
+    #[test]
+    fn scatters_only_pay_through_scatter_rules() {
+        let definition = format!(
+            "{}\n[[pay_rules]]\npattern = {{ type = \"min_count\", symbol = \"b\", count = 2 }}\npayout = 1\n",
+            TINY.replace("emoji = \"🅱️\"", "emoji = \"🅱️\"\nrole = \"scatter\"")
+        );
+        assert!(problems(&definition).contains("pay_rules[1]: scatter symbol 'b' can only be paid by a scatter rule"));
+    }
+
+    #[test]
+    fn progressive_jackpots_need_a_known_pool() {
+        let definition = format!(
+            "{}\n[[progressive_jackpots]]\npool = \"mega\"\ncontribution_rate = 0.01\ntrigger = {{ type = \"all_of_a_kind\", symbol = \"b\" }}\n",
+            TINY
+        );
+        assert!(problems(&definition).contains("progressive_jackpots[0]: unknown jackpot pool 'mega'"));
+    }
+}
@@ -1,6 +1,15 @@
+pub mod analysis;
+pub mod autoplay;
 pub mod gore_slot_machine;
+pub mod jackpot_pools;
+pub mod machine_definition;
+#[allow(clippy::module_inception)]
 pub mod slot_machine;
+pub mod registry;
 pub mod slots;
 
 pub use gore_slot_machine::*;
+pub use jackpot_pools::*;
+pub use machine_definition::*;
+pub use registry::*;
 pub use slot_machine::*;
@@ -0,0 +1,103 @@
+use super::{gore_slots_definition, load_machine_definition, JackpotPool, MachineDefinition, SlotMachine};
+use crate::services::jackpot_pools::ensure_jackpot_pool;
+use crate::services::rolling_jackpot::{load_rolling_jackpot, save_rolling_jackpot};
+use crate::{Error, PREVIOUS_ROLLING_JACKPOT};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::error;
+
+pub const DEFAULT_MACHINE_NAME: &str = "gore";
+
+/// Every slot machine the bot offers, keyed by name. Each machine has its own lock and its own
+/// rolling jackpot, so a spin on one never waits on another. The progressive pools they share
+/// live in the database.
+pub struct MachineRegistry {
+    machines: BTreeMap<String, Mutex<SlotMachine>>,
+    pools: Vec<JackpotPool>,
+}
+
+impl MachineRegistry {
+    /// Loads every `.toml`/`.json` definition in `directory`. The built-in gore machine is always
+    /// available unless a definition in the directory claims its name. Machines may feed any of
+    /// `pools`.
+    pub fn load(directory: &Path, pools: Vec<JackpotPool>) -> Result<Self, Error> {
+        let mut definitions: BTreeMap<String, MachineDefinition> = BTreeMap::new();
+        let builtin = gore_slots_definition();
+        definitions.insert(builtin.name.clone(), builtin);
+
+        if directory.is_dir() {
+            let mut paths: Vec<_> = std::fs::read_dir(directory)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("toml") | Some("json")))
+                .collect();
+            paths.sort();
+
+            let mut loaded_from: BTreeMap<String, String> = BTreeMap::new();
+            for path in paths {
+                let definition = load_machine_definition(&path)?;
+                if let Some(previous) = loaded_from.insert(definition.name.clone(), path.display().to_string()) {
+                    return Err(Error::from(format!(
+                        "Slot machine '{}' is defined in both {} and {}",
+                        definition.name,
+                        previous,
+                        path.display()
+                    )));
+                }
+                definitions.insert(definition.name.clone(), definition);
+            }
+        }
+
+        for pool in &pools {
+            if let Err(reason) = ensure_jackpot_pool(&pool.name, pool.seed) {
+                error!("Couldn't set up the {} jackpot pool: {reason:?}", pool.name);
+            }
+        }
+
+        let mut machines = BTreeMap::new();
+        for (name, definition) in definitions {
+            let slot_machine = definition.build(initial_rolling_jackpot(&name), &pools)?;
+            if let Err(reason) = save_rolling_jackpot(&name, slot_machine.rolling_jackpot()) {
+                error!("Couldn't persist rolling jackpot for {name}: {reason:?}");
+            }
+            machines.insert(name, Mutex::new(slot_machine));
+        }
+
+        Ok(MachineRegistry { machines, pools })
+    }
+
+    pub fn get(&self, name: Option<&str>) -> Result<&Mutex<SlotMachine>, Error> {
+        let name = name.unwrap_or(DEFAULT_MACHINE_NAME);
+        self.machines
+            .get(name)
+            .ok_or_else(|| Error::from(format!("There's no slot machine called '{}' on the floor.", name)))
+    }
+
+    /// Every progressive pool, in the order they're listed.
+    pub fn pools(&self) -> &[JackpotPool] {
+        &self.pools
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Mutex<SlotMachine>)> {
+        self.machines.iter().map(|(name, machine)| (name.as_str(), machine))
+    }
+}
+
+/// The persisted jackpot wins; `PREVIOUS_ROLLING_JACKPOT` only seeds the default machine the first
+/// time it is loaded, since that is the machine the variable was originally meant for.
+fn initial_rolling_jackpot(machine_name: &str) -> f64 {
+    let seed = if machine_name == DEFAULT_MACHINE_NAME {
+        *PREVIOUS_ROLLING_JACKPOT
+    } else {
+        0.0
+    };
+
+    match load_rolling_jackpot(machine_name) {
+        Ok(Some(value)) => value,
+        Ok(None) => seed,
+        Err(reason) => {
+            error!("Couldn't load rolling jackpot for {machine_name}, seeding from env: {reason:?}");
+            seed
+        }
+    }
+}
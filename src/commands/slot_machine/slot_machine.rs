@@ -1,23 +1,55 @@
-use rand::Rng;
-use std::collections::HashMap;
+use super::analysis::{analyze_pay_table, PayTableAnalysis};
+use super::jackpot_pools::JackpotPool;
+use once_cell::sync::OnceCell;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serenity::builder::{CreateEmbed, CreateEmbedFooter};
 
+/// Bumped whenever a change to the spin engine makes a seed play out differently, so spins recorded
+/// before it stop being replayed by `/verify`. Part of every machine's definition hash.
+pub const ENGINE_VERSION: u32 = 1;
+
+/// Index into the owning machine's `symbols`, in the order they were defined.
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Hash)]
-pub enum Symbol {
-    Gore,
-    Mean,
-    Magnathonk,
-    Smugbrow,
-    Smileyes,
-    Blank,
+pub struct Symbol(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolRole {
+    Standard,
+    /// Stands in for any other symbol on a payline, scatters aside.
+    Wild,
+    /// Never part of a line win; pays through `PayPattern::Scatter` rules wherever it lands.
+    Scatter,
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolDefinition {
+    pub emoji: String,
+    pub role: SymbolRole,
+    /// Line wins this symbol is part of are multiplied by this. 1 for ordinary symbols.
+    pub multiplier: u32,
+}
+
+/// Where symbols can land: one strip per reel, the rows of each strip visible after a spin and the
+/// lines that are paid across them.
+#[derive(Debug, Clone)]
+pub struct ReelLayout {
+    /// Each reel's strip, top to bottom. A stop shows `rows` consecutive symbols, wrapping around.
+    pub reels: Vec<Vec<Symbol>>,
+    pub rows: usize,
+    /// For each payline, the row (0 being the top) it crosses on each reel.
+    pub paylines: Vec<Vec<usize>>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum PayPattern {
-    FiveOfAKind(Symbol),
+    AllOfAKind(Symbol),
     ThreeOfAKind(Symbol),
     MinCountAnyDistribution(Vec<Symbol>, u8),
     MinCount(Symbol, u8),
+    /// At least this many of a scatter symbol anywhere in the window, paid on top of any line wins.
+    Scatter(Symbol, u8),
 }
 
 #[derive(Debug, Clone)]
@@ -27,100 +59,306 @@ pub struct PayRule {
     pub is_jackpot: bool,
 }
 
+/// Free spins awarded for landing enough of a bonus symbol anywhere in the window.
+#[derive(Debug, Clone)]
+pub struct BonusRound {
+    pub symbol: Symbol,
+    pub count: u8,
+    pub free_spins: u32,
+    /// Applied to every win on a free spin except the jackpot.
+    pub multiplier: u32,
+}
+
+/// A machine's stake in a shared progressive pool: how much of each paid spin goes into it, and
+/// what it takes to win it on this machine.
+#[derive(Debug, Clone)]
+pub struct ProgressiveJackpot {
+    pub pool: JackpotPool,
+    /// Share of each paid spin's cost added to the pool.
+    pub contribution_rate: f64,
+    /// Line patterns win the pool on any payline, scatter patterns with enough of the symbol
+    /// anywhere in the window, whatever its role.
+    pub trigger: PayPattern,
+}
+
+/// Which of the rules a line matches it gets paid for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvaluationMode {
+    /// Rules are checked in order and only the first match pays.
+    FirstMatch,
+    /// Every matching rule pays.
+    AllMatches,
+    /// Only the matching rule worth the most pays, jackpot rules counting at their minimum.
+    BestMatch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpinKind {
+    Paid,
+    /// Played from a bonus round: its wins are multiplied and it doesn't grow the jackpot.
+    Free,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Win {
+    /// Index into the machine's paylines, or `None` for a scatter win.
+    pub line: Option<usize>,
+    /// Index into the machine's pay table.
+    pub rule: usize,
+    pub multiplier: u32,
+    pub payout: u32,
+}
+
 #[derive(Debug)]
 pub struct PlayResult {
-    pub symbols: Vec<String>,
+    /// Everything random about the spin comes from this seed; see `SlotMachine::replay`.
+    pub seed: u64,
+    /// The visible window as emoji, one entry per row from the top.
+    pub window: Vec<Vec<String>>,
+    pub wins: Vec<Win>,
     pub payout: u32,
+    pub is_jackpot: bool,
     pub current_jackpot_value: f64,
+    pub kind: SpinKind,
+    pub free_spins_awarded: u32,
+    /// Indices into the machine's progressive jackpots whose trigger the window met.
+    pub progressive_hits: Vec<usize>,
 }
 
 pub struct SlotMachine {
+    name: String,
     pub cost_per_play: u32,
+    max_bet: u32,
     pay_table: Vec<PayRule>,
     rolling_jackpot: f64,
     min_jackpot: u32,
     jackpot_growth_rate: f64,
-    symbol_map: HashMap<Symbol, String>,
-    weighted_symbol_pool: Vec<Symbol>,
+    layout: ReelLayout,
+    symbols: Vec<SymbolDefinition>,
+    bonus: Option<BonusRound>,
+    evaluation: EvaluationMode,
+    progressive_jackpots: Vec<ProgressiveJackpot>,
+    /// Identifies the definition and engine the machine was built from; see `MachineDefinition::definition_hash`.
+    definition_hash: String,
+    analysis: OnceCell<PayTableAnalysis>,
+    rng: Box<dyn RngCore + Send>,
 }
 
-fn check_pay_pattern_match(symbols: &[Symbol], pattern: &PayPattern) -> bool {
-    match pattern {
-        PayPattern::FiveOfAKind(symbol) => symbols.iter().all(|&s| s == *symbol),
-        PayPattern::ThreeOfAKind(symbol) => symbols.iter().filter(|&&s| s == *symbol).count() >= 3,
+/// Length of the run of symbols from the leftmost reel that all satisfy `matches`, with wilds
+/// standing in for anything.
+fn leading_run(line: &[Symbol], symbols: &[SymbolDefinition], matches: impl Fn(Symbol) -> bool) -> usize {
+    line.iter()
+        .take_while(|&&s| matches(s) || symbols[s.0].role == SymbolRole::Wild)
+        .count()
+}
+
+/// How many symbols from the leftmost reel make up the match, if the line matches at all.
+/// Patterns are read left to right: counts only include the run starting on the first reel.
+fn pay_pattern_match_length(line: &[Symbol], pattern: &PayPattern, symbols: &[SymbolDefinition]) -> Option<usize> {
+    let (run, min_count) = match pattern {
+        PayPattern::AllOfAKind(symbol) => (leading_run(line, symbols, |s| s == *symbol), line.len()),
+        PayPattern::ThreeOfAKind(symbol) => (leading_run(line, symbols, |s| s == *symbol), 3),
         PayPattern::MinCountAnyDistribution(symbols_list, min_count) => {
-            let total = symbols_list
-                .iter()
-                .map(|&s| symbols.iter().filter(|&&sym| sym == s).count())
-                .sum::<usize>();
-            total >= *min_count as usize
+            (leading_run(line, symbols, |s| symbols_list.contains(&s)), *min_count as usize)
         }
-        PayPattern::MinCount(symbol, min_count) => {
-            symbols.iter().filter(|&&s| s == *symbol).count() >= *min_count as usize
+        PayPattern::MinCount(symbol, min_count) => (leading_run(line, symbols, |s| s == *symbol), *min_count as usize),
+        PayPattern::Scatter(..) => return None,
+    };
+    (run >= min_count).then_some(run)
+}
+
+/// The rules a line pays out on, each with the multiplier its winning run carries.
+pub fn matching_rules(
+    line: &[Symbol],
+    pay_table: &[PayRule],
+    symbols: &[SymbolDefinition],
+    evaluation: EvaluationMode,
+) -> Vec<(usize, u32)> {
+    let mut matches = pay_table.iter().enumerate().filter_map(|(index, rule)| {
+        let length = pay_pattern_match_length(line, &rule.pattern, symbols)?;
+        let multiplier = line[..length]
+            .iter()
+            .fold(1u32, |multiplier, s| multiplier.saturating_mul(symbols[s.0].multiplier));
+        Some((index, multiplier))
+    });
+
+    match evaluation {
+        EvaluationMode::FirstMatch => matches.next().into_iter().collect(),
+        EvaluationMode::AllMatches => matches.collect(),
+        // `max_by_key` keeps the last of equal values; reversing keeps the earliest rule on ties.
+        EvaluationMode::BestMatch => matches
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .max_by_key(|&(index, multiplier)| pay_table[index].payout.saturating_mul(multiplier))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// How many of `symbol` are showing anywhere in the window.
+pub fn count_in_window<W: AsRef<[Symbol]>>(window: &[W], symbol: Symbol) -> usize {
+    window
+        .iter()
+        .map(|reel| reel.as_ref().iter().filter(|&&s| s == symbol).count())
+        .sum()
+}
+
+/// Scatter rules a window pays, whatever order they're listed in. Each scatter symbol pays once,
+/// on the rule asking for the most of it that the window satisfies.
+pub fn scatter_rules<W: AsRef<[Symbol]>>(window: &[W], pay_table: &[PayRule]) -> Vec<usize> {
+    let mut paying: Vec<(Symbol, u8, usize)> = Vec::new();
+    for (index, rule) in pay_table.iter().enumerate() {
+        let PayPattern::Scatter(symbol, min_count) = rule.pattern else {
+            continue;
+        };
+        if count_in_window(window, symbol) < min_count as usize {
+            continue;
+        }
+        match paying.iter_mut().find(|(paying_symbol, _, _)| *paying_symbol == symbol) {
+            Some(best) if best.1 < min_count => *best = (symbol, min_count, index),
+            Some(_) => {}
+            None => paying.push((symbol, min_count, index)),
         }
     }
+
+    paying.into_iter().map(|(_, _, index)| index).collect()
 }
 
-fn single_spin(
-    weighted_symbol_pool: &[Symbol],
-    pay_table: &[PayRule],
+/// Whether a window shows `pattern`: on any of the paylines for line patterns, anywhere for
+/// scatter patterns.
+pub fn pattern_in_window<W: AsRef<[Symbol]>>(
+    window: &[W],
+    paylines: &[Vec<usize>],
+    pattern: &PayPattern,
+    symbols: &[SymbolDefinition],
+) -> bool {
+    match pattern {
+        PayPattern::Scatter(symbol, min_count) => count_in_window(window, *symbol) >= *min_count as usize,
+        _ => paylines.iter().any(|payline| {
+            let line: Vec<Symbol> = payline.iter().zip(window).map(|(&row, reel)| reel.as_ref()[row]).collect();
+            line_shows_pattern(&line, pattern, symbols)
+        }),
+    }
+}
+
+/// Whether a line pattern matches from the leftmost reel. Scatter patterns never match a line.
+pub fn line_shows_pattern(line: &[Symbol], pattern: &PayPattern, symbols: &[SymbolDefinition]) -> bool {
+    pay_pattern_match_length(line, pattern, symbols).is_some()
+}
+
+/// The symbols a payline crosses, from the leftmost reel. `window` is indexed by reel, then row.
+pub fn line_symbols(window: &[Vec<Symbol>], payline: &[usize]) -> Vec<Symbol> {
+    payline.iter().zip(window).map(|(&row, reel)| reel[row]).collect()
+}
+
+/// The `rows` symbols visible on a strip when it stops at `stop`.
+pub fn visible_symbols(strip: &[Symbol], stop: usize, rows: usize) -> Vec<Symbol> {
+    (0..rows).map(|row| strip[(stop + row) % strip.len()]).collect()
+}
+
+struct SpinOutcome {
+    payout: f64,
+    next_jackpot_value: f64,
+    window: Vec<Vec<Symbol>>,
+    wins: Vec<Win>,
+    is_jackpot: bool,
+}
+
+fn single_spin<R: Rng + ?Sized>(
+    slot_machine: &SlotMachine,
+    rng: &mut R,
     current_jackpot: f64,
-    cost_per_play: u32,
     jackpot_growth_rate: f64,
-    min_jackpot: u32,
-) -> (f64, f64, Vec<Symbol>) {
-    let mut rng = rand::rng();
-
-    let mut generated_symbols: Vec<Symbol> = (0..5)
-        .map(|_| {
-            *weighted_symbol_pool
-                .get(rng.random_range(0..weighted_symbol_pool.len()))
-                .unwrap()
-        })
+    win_multiplier: u32,
+) -> SpinOutcome {
+    let layout = &slot_machine.layout;
+    let pay_table = &slot_machine.pay_table;
+    let window: Vec<Vec<Symbol>> = layout
+        .reels
+        .iter()
+        .map(|strip| visible_symbols(strip, rng.random_range(0..strip.len()), layout.rows))
         .collect();
 
-    generated_symbols.sort_unstable();
-
     let mut spin_payout: f64 = 0.0;
     let mut jackpot_hit_this_spin = false;
+    let mut wins = Vec::new();
 
-    for rule in pay_table {
-        if check_pay_pattern_match(&generated_symbols, &rule.pattern) {
-            spin_payout = rule.payout as f64;
-            if rule.is_jackpot {
-                spin_payout = current_jackpot;
-                jackpot_hit_this_spin = true;
+    let line_wins = layout.paylines.iter().enumerate().flat_map(|(line, payline)| {
+        matching_rules(&line_symbols(&window, payline), pay_table, &slot_machine.symbols, slot_machine.evaluation)
+            .into_iter()
+            .map(move |(rule, multiplier)| (Some(line), rule, multiplier))
+    });
+    let scatter_wins = scatter_rules(&window, pay_table).into_iter().map(|rule| (None, rule, 1));
+    for (line, index, multiplier) in line_wins.chain(scatter_wins).collect::<Vec<_>>() {
+        let multiplier = multiplier.saturating_mul(win_multiplier);
+        let rule = &pay_table[index];
+        let payout = if rule.is_jackpot {
+            // However many lines hit it, there's only one jackpot to win, and it isn't multiplied.
+            if !jackpot_hit_this_spin {
+                spin_payout += current_jackpot;
             }
-            break;
-        }
+            jackpot_hit_this_spin = true;
+            current_jackpot as u32
+        } else {
+            let payout = rule.payout.saturating_mul(multiplier);
+            spin_payout += payout as f64;
+            payout
+        };
+        wins.push(Win {
+            line,
+            rule: index,
+            multiplier: if rule.is_jackpot { 1 } else { multiplier },
+            payout,
+        });
     }
 
     let next_jackpot_value: f64 = if jackpot_hit_this_spin {
-        min_jackpot as f64
+        slot_machine.min_jackpot as f64
     } else {
-        current_jackpot as f64 + (cost_per_play as f64 * jackpot_growth_rate) as f64
+        current_jackpot + (slot_machine.cost_per_play as f64 * jackpot_growth_rate)
     };
 
-    (spin_payout, next_jackpot_value, generated_symbols)
+    SpinOutcome {
+        payout: spin_payout,
+        next_jackpot_value,
+        window,
+        wins,
+        is_jackpot: jackpot_hit_this_spin,
+    }
 }
 
-pub fn generate_weighted_symbol_pool(weights: HashMap<Symbol, f64>) -> Vec<Symbol> {
-    let mut weighted_symbol_pool = Vec::new();
-    for (symbol, weight) in weights {
-        for _ in 0..weight as usize {
-            weighted_symbol_pool.push(symbol);
+/// Lays the symbols out on a strip in proportion to their weights, spread as evenly as possible
+/// so the same symbol doesn't fill the whole window (smooth weighted round-robin).
+pub fn generate_reel_strip(weights: &[u32]) -> Vec<Symbol> {
+    let total: i64 = weights.iter().map(|&weight| weight as i64).sum();
+    let mut credit = vec![0i64; weights.len()];
+    let mut strip = Vec::with_capacity(total as usize);
+    for _ in 0..total {
+        for (credit, &weight) in credit.iter_mut().zip(weights) {
+            *credit += weight as i64;
+        }
+        let mut chosen = 0;
+        for index in 1..credit.len() {
+            if credit[index] > credit[chosen] {
+                chosen = index;
+            }
         }
+        credit[chosen] -= total;
+        strip.push(Symbol(chosen));
     }
 
-    weighted_symbol_pool
+    strip
 }
 
 impl SlotMachine {
     pub fn new(
+        name: String,
         cost_per_play: u32,
         pay_table: Vec<PayRule>,
         jackpot_growth_rate: f64,
-        weighted_symbol_pool: Vec<Symbol>,
+        layout: ReelLayout,
+        symbols: Vec<SymbolDefinition>,
         previous_rolling_jackpot: f64,
     ) -> Self {
         let min_jackpot = pay_table
@@ -135,92 +373,354 @@ impl SlotMachine {
             previous_rolling_jackpot
         };
 
-        let mut symbol_map = HashMap::new();
-        symbol_map.insert(Symbol::Gore, "<:gore:854587419391164457>".to_string());
-        symbol_map.insert(Symbol::Mean, "<:mean:1260290196541280288>".to_string());
-        symbol_map.insert(
-            Symbol::Magnathonk,
-            "<:magnathonk:928158272836472872>".to_string(),
-        );
-        symbol_map.insert(
-            Symbol::Smugbrow,
-            "<:smugbrow:1013536644621664268>".to_string(),
-        );
-        symbol_map.insert(
-            Symbol::Smileyes,
-            "<:smileyes:927806099661422613>".to_string(),
-        );
-        symbol_map.insert(Symbol::Blank, "<:white:785272845890486293>".to_string());
-
         SlotMachine {
+            name,
             cost_per_play,
+            max_bet: 1,
             pay_table,
-            rolling_jackpot: rolling_jackpot,
+            rolling_jackpot,
             min_jackpot,
             jackpot_growth_rate,
-            symbol_map,
-            weighted_symbol_pool,
+            layout,
+            symbols,
+            bonus: None,
+            evaluation: EvaluationMode::FirstMatch,
+            progressive_jackpots: Vec::new(),
+            definition_hash: String::new(),
+            analysis: OnceCell::new(),
+            rng: Box::new(StdRng::from_os_rng()),
         }
     }
 
-    pub fn play(&mut self) -> PlayResult {
-        let (payout, next_jackpot_value, generated_symbols) = single_spin(
-            &self.weighted_symbol_pool,
-            &self.pay_table,
-            self.rolling_jackpot,
-            self.cost_per_play,
-            self.jackpot_growth_rate,
-            self.min_jackpot,
-        );
+    /// Replaces the OS-seeded generator that picks each spin's seed, e.g. with a fixed seed for
+    /// reproducible simulations.
+    pub fn with_rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.rng = Box::new(rng);
+        self
+    }
+
+    /// Lets players stake up to `max_bet` times the cost of a spin, for wins scaled to match.
+    pub fn with_max_bet(mut self, max_bet: u32) -> Self {
+        self.max_bet = max_bet;
+        self
+    }
+
+    pub fn with_definition_hash(mut self, definition_hash: String) -> Self {
+        self.definition_hash = definition_hash;
+        self
+    }
+
+    pub fn with_bonus(mut self, bonus: BonusRound) -> Self {
+        self.bonus = Some(bonus);
+        self.analysis = OnceCell::new();
+        self
+    }
+
+    pub fn with_evaluation(mut self, evaluation: EvaluationMode) -> Self {
+        self.evaluation = evaluation;
+        self.analysis = OnceCell::new();
+        self
+    }
+
+    pub fn with_progressive_jackpots(mut self, progressive_jackpots: Vec<ProgressiveJackpot>) -> Self {
+        self.progressive_jackpots = progressive_jackpots;
+        self.analysis = OnceCell::new();
+        self
+    }
+
+    pub fn play(&mut self, kind: SpinKind) -> PlayResult {
+        let seed = self.rng.next_u64();
+        self.play_seeded(seed, kind)
+    }
+
+    /// Plays the spin `seed` produces and moves the rolling jackpot on, for seeds that come from
+    /// somewhere other than the machine's own generator (see `services::fairness`).
+    pub fn play_seeded(&mut self, seed: u64, kind: SpinKind) -> PlayResult {
+        let play_result = self.replay(seed, self.rolling_jackpot, kind);
+        self.rolling_jackpot = play_result.current_jackpot_value;
+        play_result
+    }
+
+    /// Recomputes the spin `seed` produces on this machine, as if the rolling jackpot stood at
+    /// `rolling_jackpot`. Doesn't change the machine. The seed starts a ChaCha8 generator
+    /// (`ChaCha8Rng::seed_from_u64`), which picks each reel's stop, left to right, with
+    /// `random_range(0..strip length)`; the window shows the stop and the symbols below it, wrapping
+    /// round the strip.
+    pub fn replay(&self, seed: u64, rolling_jackpot: f64, kind: SpinKind) -> PlayResult {
+        let (jackpot_growth_rate, win_multiplier) = match kind {
+            SpinKind::Paid => (self.jackpot_growth_rate, 1),
+            SpinKind::Free => (0.0, self.bonus.as_ref().map_or(1, |bonus| bonus.multiplier)),
+        };
+        // ChaCha8 rather than StdRng: its output is fixed across rand releases, so old seeds keep replaying.
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let outcome = single_spin(self, &mut rng, rolling_jackpot, jackpot_growth_rate, win_multiplier);
+        let free_spins_awarded = match &self.bonus {
+            Some(bonus) if count_in_window(&outcome.window, bonus.symbol) >= bonus.count as usize => bonus.free_spins,
+            _ => 0,
+        };
+        let progressive_hits = self
+            .progressive_jackpots
+            .iter()
+            .enumerate()
+            .filter(|(_, progressive)| {
+                pattern_in_window(&outcome.window, &self.layout.paylines, &progressive.trigger, &self.symbols)
+            })
+            .map(|(index, _)| index)
+            .collect();
 
         // Round the jackpot to 8 decimal places before storing
         let factor = 100_000_000.0; // 10^8
-        self.rolling_jackpot = (next_jackpot_value * factor).round() / factor;
+        let current_jackpot_value = (outcome.next_jackpot_value * factor).round() / factor;
 
-        let display_symbols: Vec<String> = generated_symbols
-            .iter()
-            .map(|&s| self.get_symbol_string(s))
+        let display_window: Vec<Vec<String>> = (0..self.layout.rows)
+            .map(|row| outcome.window.iter().map(|reel| self.get_symbol_string(reel[row])).collect())
             .collect();
-        let payout_u32 = payout as u32;
+        let payout_u32 = outcome.payout as u32;
 
         PlayResult {
-            symbols: display_symbols,
+            seed,
+            window: display_window,
+            wins: outcome.wins,
             payout: payout_u32,
-            current_jackpot_value: self.rolling_jackpot,
+            is_jackpot: outcome.is_jackpot,
+            current_jackpot_value,
+            kind,
+            free_spins_awarded,
+            progressive_hits,
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn definition_hash(&self) -> &str {
+        &self.definition_hash
+    }
+
+    pub fn max_bet(&self) -> u32 {
+        self.max_bet
+    }
+
+    pub fn rolling_jackpot(&self) -> f64 {
+        self.rolling_jackpot
+    }
+
+    /// Puts the rolling jackpot back to where it stood before spins that were called off.
+    pub fn rewind_rolling_jackpot(&mut self, rolling_jackpot: f64) {
+        self.rolling_jackpot = rolling_jackpot;
+    }
+
+    pub fn min_jackpot(&self) -> u32 {
+        self.min_jackpot
+    }
+
+    pub fn jackpot_growth_rate(&self) -> f64 {
+        self.jackpot_growth_rate
+    }
+
+    pub fn reel_count(&self) -> usize {
+        self.layout.reels.len()
+    }
+
+    pub fn layout(&self) -> &ReelLayout {
+        &self.layout
+    }
+
+    pub fn symbols(&self) -> &[SymbolDefinition] {
+        &self.symbols
+    }
+
+    pub fn pay_table(&self) -> &[PayRule] {
+        &self.pay_table
+    }
+
+    pub fn bonus(&self) -> Option<&BonusRound> {
+        self.bonus.as_ref()
+    }
+
+    pub fn evaluation(&self) -> EvaluationMode {
+        self.evaluation
+    }
+
+    pub fn progressive_jackpots(&self) -> &[ProgressiveJackpot] {
+        &self.progressive_jackpots
+    }
+
+    /// Exact odds for this machine's paytable, worked out once and reused afterwards.
+    pub fn analysis(&self) -> &PayTableAnalysis {
+        self.analysis.get_or_init(|| analyze_pay_table(self))
+    }
+
+    /// The most a single play of this kind can pay out right now, jackpot included.
+    pub fn max_payout(&self, kind: SpinKind) -> f64 {
+        let analysis = self.analysis();
+        let win_multiplier = match (kind, &self.bonus) {
+            (SpinKind::Free, Some(bonus)) => bonus.multiplier as f64,
+            _ => 1.0,
+        };
+        let with_jackpot = if analysis.jackpot_probability > 0.0 {
+            analysis.max_payout_beside_jackpot * win_multiplier + self.rolling_jackpot
+        } else {
+            0.0
+        };
+        f64::max(analysis.max_payout_without_jackpot * win_multiplier, with_jackpot)
+    }
+
     fn get_symbol_string(&self, symbol: Symbol) -> String {
-        self.symbol_map
-            .get(&symbol)
-            .cloned()
+        self.symbols
+            .get(symbol.0)
+            .map(|definition| definition.emoji.clone())
             .unwrap_or_else(|| "Unknown".to_string())
     }
 
     pub fn get_pay_table_embed(&self) -> CreateEmbed {
+        let analysis = self.analysis();
+        let footer = format!(
+            "Pays left to right on {} lines, {}\nRTP: {:.2}% | Wins 1 in {:.1} spins | Volatility: {:.1}\nJackpot every ~{:.0} spins, averaging {:.0} Libcoin",
+            self.layout.paylines.len(),
+            match self.evaluation {
+                EvaluationMode::FirstMatch => "first matching rule only",
+                EvaluationMode::AllMatches => "every matching rule adds up",
+                EvaluationMode::BestMatch => "best matching rule only",
+            },
+            analysis.rtp * 100.0,
+            1.0 / analysis.hit_frequency,
+            analysis.volatility,
+            analysis.jackpot_cycle_length,
+            analysis.expected_jackpot,
+        );
         let embed = CreateEmbed::new()
-            .title(format!("Pay Table - {} Libcoin a Spin", self.cost_per_play))
+            .title(if self.max_bet > 1 {
+                format!(
+                    "Pay Table ({}) - {} Libcoin a Spin, Bets up to x{}",
+                    self.name, self.cost_per_play, self.max_bet
+                )
+            } else {
+                format!("Pay Table ({}) - {} Libcoin a Spin", self.name, self.cost_per_play)
+            })
             .color(0x5b9e48)
-            .footer(CreateEmbedFooter::new("RTP: ~97%"))
-            .fields(self.pay_table.iter().map(|rule| {
+            .footer(CreateEmbedFooter::new(footer))
+            .fields(self.pay_table.iter().zip(&analysis.rule_hit_probabilities).map(|(rule, probability)| {
                 let title = self.get_pay_rule_title(rule);
                 let payout = if rule.is_jackpot {
                     self.rolling_jackpot.to_string()
                 } else {
                     rule.payout.to_string()
                 };
-                (title, payout, false)
-            }).collect::<Vec<_>>());
+                let odds = if *probability > 0.0 {
+                    format!("1 in {:.0} spins", 1.0 / probability)
+                } else {
+                    match self.evaluation {
+                        EvaluationMode::FirstMatch => "never, an earlier rule always pays first".to_string(),
+                        EvaluationMode::AllMatches => "never".to_string(),
+                        EvaluationMode::BestMatch => "never, a better rule always pays instead".to_string(),
+                    }
+                };
+                (title, format!("{} ({})", payout, odds), false)
+            }).collect::<Vec<_>>())
+            .field("Paylines", self.describe_paylines(), false);
 
-        embed
+        let embed = match self.describe_special_symbols() {
+            Some(special_symbols) => embed.field("Special Symbols", special_symbols, false),
+            None => embed,
+        };
+
+        let embed = match self.describe_progressive_jackpots() {
+            Some(progressive_jackpots) => embed.field("Progressive Jackpots", progressive_jackpots, false),
+            None => embed,
+        };
+
+        match &self.bonus {
+            Some(bonus) => embed.field(
+                "Bonus",
+                format!(
+                    "{}x {} anywhere: {} free spins, wins x{} (1 in {:.0} spins)",
+                    bonus.count,
+                    self.get_symbol_string(bonus.symbol),
+                    bonus.free_spins,
+                    bonus.multiplier,
+                    1.0 / analysis.bonus_probability
+                ),
+                false,
+            ),
+            None => embed,
+        }
+    }
+
+    fn describe_special_symbols(&self) -> Option<String> {
+        let descriptions: Vec<String> = self
+            .symbols
+            .iter()
+            .filter_map(|symbol| {
+                let role = match symbol.role {
+                    SymbolRole::Standard => None,
+                    SymbolRole::Wild => Some("Wild, stands in for any symbol on a line"),
+                    SymbolRole::Scatter => Some("Scatter, pays wherever it lands"),
+                };
+                let multiplier = (symbol.multiplier > 1).then(|| format!("multiplies line wins it's part of by {}", symbol.multiplier));
+                match (role, multiplier) {
+                    (None, None) => None,
+                    (Some(role), None) => Some(format!("{}: {}", symbol.emoji, role)),
+                    (None, Some(multiplier)) => Some(format!("{}: {}", symbol.emoji, multiplier)),
+                    (Some(role), Some(multiplier)) => Some(format!("{}: {}, {}", symbol.emoji, role, multiplier)),
+                }
+            })
+            .collect();
+
+        (!descriptions.is_empty()).then(|| descriptions.join("\n"))
+    }
+
+    fn describe_progressive_jackpots(&self) -> Option<String> {
+        let analysis = self.analysis();
+        let descriptions: Vec<String> = self
+            .progressive_jackpots
+            .iter()
+            .zip(&analysis.progressive_hit_probabilities)
+            .map(|(progressive, probability)| {
+                let odds = if *probability > 0.0 {
+                    format!("1 in {:.0} spins", 1.0 / probability)
+                } else {
+                    "never".to_string()
+                };
+                format!(
+                    "{}: {} ({}), fed {}% of each spin",
+                    progressive.pool.title(),
+                    self.get_pattern_title(&progressive.trigger),
+                    odds,
+                    progressive.contribution_rate * 100.0
+                )
+            })
+            .collect();
+
+        (!descriptions.is_empty()).then(|| descriptions.join("\n"))
+    }
+
+    /// One line per payline, listing the row it crosses on each reel counting from 1 at the top.
+    fn describe_paylines(&self) -> String {
+        self.layout
+            .paylines
+            .iter()
+            .enumerate()
+            .map(|(index, payline)| {
+                let rows: Vec<String> = payline.iter().map(|row| (row + 1).to_string()).collect();
+                format!("Line {}: {}", index + 1, rows.join("-"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
     fn get_pay_rule_title(&self, rule: &PayRule) -> String {
-        match &rule.pattern {
-            PayPattern::FiveOfAKind(symbol) => {
-                let jackpot_indicator = if rule.is_jackpot { " (Jackpot)" } else { "" }; 
-                self.get_symbol_string(*symbol).repeat(5) + jackpot_indicator
-            },
+        let jackpot_indicator = match rule.pattern {
+            PayPattern::AllOfAKind(_) | PayPattern::Scatter(..) if rule.is_jackpot => " (Jackpot)",
+            _ => "",
+        };
+        self.get_pattern_title(&rule.pattern) + jackpot_indicator
+    }
+
+    fn get_pattern_title(&self, pattern: &PayPattern) -> String {
+        match pattern {
+            PayPattern::AllOfAKind(symbol) => self.get_symbol_string(*symbol).repeat(self.reel_count()),
             PayPattern::ThreeOfAKind(symbol) => self.get_symbol_string(*symbol).repeat(3),
             PayPattern::MinCountAnyDistribution(symbols, min_count) => {
                 let symbol_str = symbols
@@ -231,6 +731,278 @@ impl SlotMachine {
                 format!("{}x any of [{}]", min_count, symbol_str)
             }
             PayPattern::MinCount(symbol, min_count) => self.get_symbol_string(*symbol).repeat(*min_count as usize),
+            PayPattern::Scatter(symbol, min_count) => {
+                format!("{}x {} anywhere", min_count, self.get_symbol_string(*symbol))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::slot_machine::gore_slot_machine::gore_slots_definition;
+    use crate::commands::slot_machine::jackpot_pools::default_jackpot_pools;
+
+    const A: Symbol = Symbol(0);
+    const B: Symbol = Symbol(1);
+    /// Wild, tripling the wins it's part of.
+    const W: Symbol = Symbol(2);
+    const S: Symbol = Symbol(3);
+
+    fn gore() -> SlotMachine {
+        gore_slots_definition()
+            .build(0.0, &default_jackpot_pools().unwrap())
+            .unwrap()
+    }
+
+    fn symbols() -> Vec<SymbolDefinition> {
+        let symbol = |emoji: &str, role, multiplier| SymbolDefinition {
+            emoji: emoji.to_string(),
+            role,
+            multiplier,
+        };
+        vec![
+            symbol("a", SymbolRole::Standard, 1),
+            symbol("b", SymbolRole::Standard, 1),
+            symbol("w", SymbolRole::Wild, 3),
+            symbol("s", SymbolRole::Scatter, 1),
+        ]
+    }
+
+    fn rule(pattern: PayPattern, payout: u32) -> PayRule {
+        PayRule {
+            pattern,
+            payout,
+            is_jackpot: false,
+        }
+    }
+
+    #[test]
+    fn lines_pay_from_the_leftmost_reel() {
+        let symbols = symbols();
+        let pay_table = vec![rule(PayPattern::ThreeOfAKind(A), 5)];
+        let pays = |line: &[Symbol]| matching_rules(line, &pay_table, &symbols, EvaluationMode::FirstMatch);
+        assert_eq!(pays(&[A, A, A, B, B]), vec![(0, 1)]);
+        assert_eq!(pays(&[A, A, A, A, A]), vec![(0, 1)]);
+        // Three of them, but not starting from the first reel.
+        assert!(pays(&[B, A, A, A, B]).is_empty());
+        assert!(pays(&[A, A, B, A, A]).is_empty());
+    }
+
+    #[test]
+    fn a_stop_shows_the_symbols_below_it_wrapping_round() {
+        assert_eq!(visible_symbols(&[A, B, S], 0, 3), vec![A, B, S]);
+        assert_eq!(visible_symbols(&[A, B, S], 2, 3), vec![S, A, B]);
+        assert_eq!(visible_symbols(&[A, B, S], 1, 1), vec![B]);
+    }
+
+    #[test]
+    fn paylines_cross_the_rows_they_list() {
+        let symbols = symbols();
+        // Indexed by reel, then row: `a` runs down the diagonal.
+        let window = vec![vec![A, B, B], vec![B, A, B], vec![B, B, A]];
+        assert_eq!(line_symbols(&window, &[0, 1, 2]), vec![A, A, A]);
+        assert_eq!(line_symbols(&window, &[0, 0, 0]), vec![A, B, B]);
+
+        let all_a = PayPattern::AllOfAKind(A);
+        assert!(pattern_in_window(&window, &[vec![1, 1, 1], vec![0, 1, 2]], &all_a, &symbols));
+        assert!(!pattern_in_window(&window, &[vec![0, 0, 0], vec![2, 1, 0]], &all_a, &symbols));
+    }
+
+    #[test]
+    fn wilds_stand_in_and_multiply() {
+        let symbols = symbols();
+        let pay_table = vec![rule(PayPattern::ThreeOfAKind(A), 5)];
+        let pays = |line: &[Symbol]| matching_rules(line, &pay_table, &symbols, EvaluationMode::FirstMatch);
+        assert_eq!(pays(&[A, W, A, B, B]), vec![(0, 3)]);
+        assert_eq!(pays(&[W, W, A, B, B]), vec![(0, 9)]);
+        // Only the winning run is multiplied.
+        assert_eq!(pays(&[A, A, A, B, W]), vec![(0, 1)]);
+        // Scatters aren't part of a line, so they break the run.
+        assert!(pays(&[A, S, A, B, B]).is_empty());
+    }
+
+    #[test]
+    fn evaluation_modes_pick_which_rules_pay() {
+        let symbols = symbols();
+        let pay_table = vec![
+            rule(PayPattern::MinCount(A, 3), 5),
+            rule(PayPattern::AllOfAKind(A), 20),
+            rule(PayPattern::MinCount(A, 2), 1),
+        ];
+        let line = [A, A, A, A, A];
+        let pays = |evaluation| matching_rules(&line, &pay_table, &symbols, evaluation);
+        assert_eq!(pays(EvaluationMode::FirstMatch), vec![(0, 1)]);
+        assert_eq!(pays(EvaluationMode::AllMatches), vec![(0, 1), (1, 1), (2, 1)]);
+        assert_eq!(pays(EvaluationMode::BestMatch), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn best_match_weighs_multipliers_and_keeps_the_earliest_tie() {
+        let symbols = symbols();
+        let pay_table = vec![rule(PayPattern::AllOfAKind(A), 10), rule(PayPattern::MinCount(A, 2), 10)];
+        let best = |line: &[Symbol]| matching_rules(line, &pay_table, &symbols, EvaluationMode::BestMatch);
+        assert_eq!(best(&[A, A, A, A, A]), vec![(0, 1)]);
+
+        // 10 tripled by the wild beats a bigger pay whose run stops before it.
+        let pay_table = vec![
+            rule(PayPattern::MinCount(A, 2), 20),
+            rule(PayPattern::MinCountAnyDistribution(vec![A, B], 5), 10),
+        ];
+        let best = |line: &[Symbol]| matching_rules(line, &pay_table, &symbols, EvaluationMode::BestMatch);
+        assert_eq!(best(&[A, A, B, W, B]), vec![(1, 3)]);
+        assert_eq!(best(&[A, A, B, B, B]), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn scatters_pay_anywhere_on_their_best_rule() {
+        let pay_table = vec![
+            rule(PayPattern::Scatter(S, 2), 2),
+            rule(PayPattern::Scatter(S, 3), 10),
+            rule(PayPattern::AllOfAKind(A), 50),
+        ];
+        let two = vec![vec![S, A], vec![B, B], vec![A, S]];
+        let three = vec![vec![S, A], vec![B, S], vec![A, S]];
+        assert_eq!(count_in_window(&three, S), 3);
+        assert_eq!(scatter_rules(&two, &pay_table), vec![0]);
+        assert_eq!(scatter_rules(&three, &pay_table), vec![1]);
+        assert!(scatter_rules(&[vec![A, B], vec![B, S]], &pay_table).is_empty());
+
+        // Whatever order the rules are listed in.
+        let reversed: Vec<PayRule> = pay_table.into_iter().rev().collect();
+        assert_eq!(scatter_rules(&three, &reversed), vec![1]);
+    }
+
+    #[test]
+    fn scatter_wins_are_paid_on_top_of_line_wins() {
+        let jackpot = PayRule {
+            pattern: PayPattern::AllOfAKind(B),
+            payout: 1000,
+            is_jackpot: true,
+        };
+        // Every reel shows `a` over `s`, so the top line pays and three scatters land.
+        let machine = SlotMachine::new(
+            "scatters".to_string(),
+            1,
+            vec![jackpot, rule(PayPattern::AllOfAKind(A), 5), rule(PayPattern::Scatter(S, 3), 7)],
+            0.0,
+            ReelLayout {
+                reels: vec![vec![A, S]; 3],
+                rows: 2,
+                paylines: vec![vec![0, 0, 0]],
+            },
+            symbols(),
+            0.0,
+        );
+        let play_result = (0..64)
+            .map(|seed| machine.replay(seed, 1000.0, SpinKind::Paid))
+            .find(|play_result| play_result.window[0] == ["a", "a", "a"])
+            .expect("some seed stops every reel on `a`");
+        assert_eq!(play_result.payout, 12);
+        assert_eq!(
+            play_result.wins,
+            vec![
+                Win {
+                    line: Some(0),
+                    rule: 1,
+                    multiplier: 1,
+                    payout: 5
+                },
+                Win {
+                    line: None,
+                    rule: 2,
+                    multiplier: 1,
+                    payout: 7
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_plays_the_same_spin_as_play_seeded() {
+        let mut machine = gore();
+        for seed in [0, 1, 42, 0xdead_beef, u64::MAX] {
+            let jackpot_before = machine.rolling_jackpot();
+            let played = machine.play_seeded(seed, SpinKind::Paid);
+            let replayed = machine.replay(seed, jackpot_before, SpinKind::Paid);
+            assert_eq!(replayed.window, played.window);
+            assert_eq!(replayed.wins, played.wins);
+            assert_eq!(replayed.payout, played.payout);
+            assert_eq!(replayed.progressive_hits, played.progressive_hits);
+            assert_eq!(replayed.current_jackpot_value, played.current_jackpot_value);
+            assert_eq!(machine.rolling_jackpot(), played.current_jackpot_value);
         }
     }
+
+    #[test]
+    fn replay_leaves_the_machine_alone() {
+        let machine = gore();
+        let jackpot = machine.rolling_jackpot();
+        let replayed = machine.replay(7, jackpot, SpinKind::Paid);
+        assert_eq!(machine.rolling_jackpot(), jackpot);
+        // A paid spin that misses the jackpot grows it by its share of the cost.
+        assert!(!replayed.is_jackpot);
+        assert!(replayed.current_jackpot_value > jackpot);
+    }
+
+    #[test]
+    fn a_seeded_generator_plays_the_same_spins() {
+        let mut first = gore().with_rng(StdRng::seed_from_u64(1));
+        let mut second = gore().with_rng(StdRng::seed_from_u64(1));
+        for _ in 0..50 {
+            let (a, b) = (first.play(SpinKind::Paid), second.play(SpinKind::Paid));
+            assert_eq!(a.seed, b.seed);
+            assert_eq!(a.window, b.window);
+            assert_eq!(a.payout, b.payout);
+        }
+        assert_eq!(first.rolling_jackpot(), second.rolling_jackpot());
+    }
+
+    #[test]
+    fn bonus_symbols_award_free_spins_that_pay_more() {
+        let jackpot = PayRule {
+            pattern: PayPattern::AllOfAKind(B),
+            payout: 1000,
+            is_jackpot: true,
+        };
+        // Every reel shows `a` and `s` one above the other, so every spin lands three bonus symbols.
+        let machine = SlotMachine::new(
+            "bonus".to_string(),
+            1,
+            vec![jackpot, rule(PayPattern::AllOfAKind(A), 5)],
+            0.0,
+            ReelLayout {
+                reels: vec![vec![A, S]; 3],
+                rows: 2,
+                paylines: vec![vec![0, 0, 0]],
+            },
+            symbols(),
+            0.0,
+        )
+        .with_bonus(BonusRound {
+            symbol: S,
+            count: 3,
+            free_spins: 5,
+            multiplier: 2,
+        });
+        let seed = (0..64)
+            .find(|&seed| machine.replay(seed, 1000.0, SpinKind::Paid).payout > 0)
+            .expect("some seed stops every reel on `a`");
+
+        let paid = machine.replay(seed, 1000.0, SpinKind::Paid);
+        assert_eq!((paid.payout, paid.free_spins_awarded), (5, 5));
+        let free = machine.replay(seed, 1000.0, SpinKind::Free);
+        assert_eq!((free.payout, free.free_spins_awarded), (10, 5));
+        assert_eq!(free.wins[0].multiplier, 2);
+    }
+
+    #[test]
+    fn free_spins_dont_grow_the_jackpot() {
+        let machine = gore();
+        let jackpot = machine.rolling_jackpot();
+        let replayed = machine.replay(7, jackpot, SpinKind::Free);
+        assert!(!replayed.is_jackpot);
+        assert_eq!(replayed.current_jackpot_value, jackpot);
+    }
 }
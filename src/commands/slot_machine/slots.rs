@@ -1,71 +1,433 @@
-use super::{generate_gore_slots, PlayResult, SlotMachine};
-use crate::services::libcoin::{
-    deduct_libcoin, get_libcoin_balance, get_user_transactions, grant_libcoin,
-};
-use crate::{Context, Error, PREVIOUS_ROLLING_JACKPOT};
-use once_cell::sync::Lazy;
+use super::{PlayResult, ProgressiveJackpot, SpinKind};
+use crate::commands::buttons::{wait_for_press, BUTTON_WINDOW};
+use crate::commands::hall_of_fame::announce_jackpot;
+use crate::services::fairness::{next_fair_spins, FairSpin};
+use crate::services::free_spins::{award_free_spins, free_spins_remaining, machine_with_free_spins, take_free_spins};
+use crate::services::hall_of_fame::{record_jackpot_hit, JackpotHit};
+use crate::services::jackpot_pools::{jackpot_pool_values, settle_jackpot_pools, PoolSettlement};
+use crate::services::rolling_jackpot::save_rolling_jackpot;
+use crate::services::settlement::{call_off, describe_payout, ensure_house_can_cover, settle, stake_wager, PayoutStatus};
+use crate::services::spin_history::{format_seed, record_spin};
+use crate::{Context, Error, MR_HOUSE_ID};
+use chrono::Utc;
 use poise::serenity_prelude as serenity;
 use poise::CreateReply;
-use serenity::builder::{CreateEmbed, CreateEmbedFooter};
-use std::sync::Mutex;
+use serenity::builder::{CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter};
+use std::time::{Duration, Instant};
+use tracing::error;
 
-pub static GORE_SLOT_MACHINE: Lazy<Mutex<SlotMachine>> =
-    Lazy::new(|| Mutex::new(generate_gore_slots(*PREVIOUS_ROLLING_JACKPOT)));
-
-const MR_HOUSE_ID: u64 = 1382600478206066769;
 const DEDUCT_MESSAGE: &str = "Playing the slot machine";
 const GRANT_MESSAGE: &str = "Winning from the slot machine";
+pub const MAX_SPINS_PER_COMMAND: u32 = 10;
+const SPINNING_SYMBOL: &str = "🌀";
+const REVEAL_INTERVAL: Duration = Duration::from_millis(800);
+const SPIN_AGAIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// One spin of a batch. `payout` is the spin's own payout scaled by the bet, plus any progressive
+/// jackpots in `progressive_wins`.
+pub struct SpinReport {
+    pub play_result: PlayResult,
+    pub fair_spin: FairSpin,
+    pub payout: u64,
+    pub progressive_wins: Vec<(String, u64)>,
+}
+
+/// Spins played on one machine in one go, all paid or all free, staked and settled as a single
+/// wager so the bank is only asked to move money twice.
+pub struct SpinBatch {
+    pub machine_name: String,
+    pub kind: SpinKind,
+    pub bet: u32,
+    pub stake: f64,
+    pub spins: Vec<SpinReport>,
+    pub payout: u64,
+    pub payout_status: PayoutStatus,
+    pub free_spins_awarded: u32,
+    pub free_spins_left: Option<u32>,
+    /// Spins that were drawn but couldn't be settled with the progressive pools, and were handed back.
+    pub called_off: u32,
+    pub jackpot_hits: Vec<JackpotHit>,
+}
+
+pub async fn autocomplete_machine(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+    ctx.data()
+        .slot_machines
+        .iter()
+        .filter(|(name, _)| name.to_lowercase().contains(&partial))
+        .map(|(name, slot_machine)| {
+            let (cost_per_play, max_bet) = {
+                let slot_machine = slot_machine.lock().unwrap();
+                (slot_machine.cost_per_play, slot_machine.max_bet())
+            };
+            let label = if max_bet > 1 {
+                format!("{name} ({cost_per_play} Libcoin a spin, bets up to x{max_bet})")
+            } else {
+                format!("{name} ({cost_per_play} Libcoin a spin)")
+            };
+            serenity::AutocompleteChoice::new(label, name)
+        })
+        .collect()
+}
 
 #[poise::command(
     slash_command,
-    description_localized("en-US", "Spin the slot machine for a chance to win Libcoin! Costs 10 Libcoin per spin."),
-    description_localized("fr", "Faites tourner la machine à sous pour tenter de gagner des Libcoins! Coût : 10 Libcoins par tour."),
-    description_localized("es-ES", "Gira la ruleta para ganar Libcoin! 10 Libcoin por giro!")
+    description_localized("en-US", "Spin a slot machine for a chance to win Libcoin!"),
+    description_localized("fr", "Faites tourner une machine à sous pour tenter de gagner des Libcoins!"),
+    description_localized("es-ES", "Gira una tragaperras para ganar Libcoin!")
 )]
-pub async fn slots(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn slots(
+    ctx: Context<'_>,
+    #[description = "Which slot machine to play"]
+    #[description_localized("fr", "La machine à sous à utiliser")]
+    #[description_localized("es-ES", "La tragaperras que quieres jugar")]
+    #[autocomplete = "autocomplete_machine"]
+    machine: Option<String>,
+    #[description = "Multiple of the machine's cost to stake on each spin; wins scale to match"]
+    #[description_localized("fr", "Multiple du prix de la machine misé sur chaque tour; les gains suivent")]
+    #[description_localized("es-ES", "Múltiplo del precio de la máquina apostado en cada tirada; los premios también")]
+    #[min = 1]
+    bet: Option<u32>,
+    #[description = "How many spins to play in one go"]
+    #[description_localized("fr", "Combien de tours jouer d'un coup")]
+    #[description_localized("es-ES", "Cuántas tiradas jugar de una vez")]
+    #[min = 1]
+    #[max = 10]
+    spins: Option<u32>,
+) -> Result<(), Error> {
+    let (bet, spins) = (bet.unwrap_or(1), spins.unwrap_or(1));
+    let started_at = Instant::now();
+    let spin_again_id = format!("{}-spin-again", ctx.id());
+
+    loop {
+        let reply = ctx
+            .send(CreateReply {
+                embeds: vec![spinning_embed(None, 0)],
+                ..Default::default()
+            })
+            .await?;
+        let batch = match play_batch(ctx, machine.clone(), bet, spins).await {
+            Ok(batch) => batch,
+            Err(reason) => {
+                // The error gets a reply of its own; reels left spinning above it would never stop.
+                if let Err(delete_reason) = reply.delete(ctx).await {
+                    error!("Couldn't clear away the spinning reels: {delete_reason:?}");
+                }
+                return Err(reason);
+            }
+        };
+
+        let reel_count = batch.spins[0].play_result.window[0].len();
+        for revealed_reels in 1..reel_count {
+            tokio::time::sleep(REVEAL_INTERVAL).await;
+            let reveal = CreateReply {
+                embeds: vec![spinning_embed(Some(&batch), revealed_reels)],
+                ..Default::default()
+            };
+            if let Err(reason) = reply.edit(ctx, reveal).await {
+                error!("Couldn't reveal the next reel: {reason:?}");
+                break;
+            }
+        }
+        tokio::time::sleep(REVEAL_INTERVAL).await;
+
+        let can_spin_again = started_at.elapsed() < BUTTON_WINDOW;
+        let components = can_spin_again.then(|| {
+            vec![CreateActionRow::Buttons(vec![CreateButton::new(&spin_again_id)
+                .style(serenity::ButtonStyle::Primary)
+                .label("Spin again")])]
+        });
+        reply
+            .edit(
+                ctx,
+                CreateReply {
+                    embeds: vec![build_result_embed(&batch)],
+                    components,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        for hit in &batch.jackpot_hits {
+            announce_jackpot(ctx, hit).await;
+        }
+
+        if !can_spin_again {
+            return Ok(());
+        }
+        let pressed = wait_for_press(ctx, std::slice::from_ref(&spin_again_id), SPIN_AGAIN_TIMEOUT).await;
+        // Only the latest result keeps its button.
+        reply
+            .edit(
+                ctx,
+                CreateReply {
+                    embeds: vec![build_result_embed(&batch)],
+                    components: Some(Vec::new()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        if pressed.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+/// The reels still turning: `revealed_reels` of them have stopped on `batch`'s results, and
+/// without a batch none have.
+fn spinning_embed(batch: Option<&SpinBatch>, revealed_reels: usize) -> CreateEmbed {
+    let embed = CreateEmbed::new().color(0x5b9e48).title("🎰 Spinning...");
+    let Some(batch) = batch else {
+        return embed.description("The reels are spinning...");
+    };
+
+    let mask = |row: &[String]| -> String {
+        row.iter()
+            .enumerate()
+            .map(|(reel, symbol)| if reel < revealed_reels { symbol.as_str() } else { SPINNING_SYMBOL })
+            .collect()
+    };
+    match batch.spins.as_slice() {
+        [report] => {
+            let grid: Vec<String> = report.play_result.window.iter().map(|row| mask(row)).collect();
+            embed.field("Spin Result", grid.join("\n"), false)
+        }
+        reports => {
+            let spin_lines: Vec<String> = reports
+                .iter()
+                .enumerate()
+                .map(|(index, report)| {
+                    let window = &report.play_result.window;
+                    format!("`#{}` {}", index + 1, mask(&window[window.len() / 2]))
+                })
+                .collect();
+            embed.description(spin_lines.join("\n"))
+        }
+    }
+}
+
+/// Plays `spins` spins at `bet` times the machine's cost. Without a machine asked for, a bonus
+/// round still in progress is played before anything else, and a bonus round is always played out
+/// at a bet of 1, as many of its free spins at a time as `spins` allows.
+pub async fn play_batch(ctx: Context<'_>, machine: Option<String>, bet: u32, spins: u32) -> Result<SpinBatch, Error> {
     let user_id = ctx.author().id.get();
-    let play_cost = {
-        let slot_machine = GORE_SLOT_MACHINE.lock().unwrap();
-        slot_machine.cost_per_play
+    let bank = &ctx.data().bank;
+    let machine = match machine {
+        Some(machine) => Some(machine),
+        None => machine_with_free_spins(user_id).unwrap_or_else(|reason| {
+            error!("Couldn't look up free spins for {user_id}: {reason:?}");
+            None
+        }),
+    };
+    let slot_machine = ctx.data().slot_machines.get(machine.as_deref())?;
+    let (machine_name, cost_per_play, max_bet) = {
+        let slot_machine = slot_machine.lock().unwrap();
+        (slot_machine.name().to_string(), slot_machine.cost_per_play, slot_machine.max_bet())
+    };
+
+    if bet == 0 || bet > max_bet {
+        return Err(Error::from(format!(
+            "{} takes bets from 1 to {} times its {} Libcoin spin.",
+            machine_name, max_bet, cost_per_play
+        )));
+    }
+    if spins == 0 || spins > MAX_SPINS_PER_COMMAND {
+        return Err(Error::from(format!(
+            "You can play from 1 to {} spins at a time.",
+            MAX_SPINS_PER_COMMAND
+        )));
+    }
+
+    let (kind, bet, spins) = match free_spins_remaining(user_id, &machine_name) {
+        Ok(remaining) if remaining > 0 => (SpinKind::Free, 1, spins.min(remaining)),
+        Ok(_) => (SpinKind::Paid, bet, spins),
+        Err(_) => return Err(Error::from("Sorry, I couldn't check whether you have free spins left.")),
+    };
+    let (max_payout, progressive_jackpots) = {
+        let slot_machine = slot_machine.lock().unwrap();
+        (slot_machine.max_payout(kind), slot_machine.progressive_jackpots().to_vec())
+    };
+    let pool_values = jackpot_pool_values()
+        .map_err(|_| Error::from("Sorry, I couldn't check the progressive jackpots."))?;
+    let pool_exposure: f64 = progressive_jackpots
+        .iter()
+        .map(|progressive| pool_values.get(&progressive.pool.name).copied().unwrap_or(progressive.pool.seed as f64))
+        .sum();
+
+    let spin_cost = cost_per_play as u64 * bet as u64;
+    let exposure = max_payout * bet as f64 * spins as f64 + pool_exposure;
+
+    // Drawn before any money moves: a nonce skipped by a failed wager is harmless, a staked
+    // wager without a seed is not.
+    let mut fair_spins = next_fair_spins(user_id, spins)
+        .map_err(|_| Error::from("Sorry, I couldn't draw up the seeds for your spins."))?;
+
+    let game = format!("slots:{machine_name}");
+    let (wager_id, stake, mut free_spins_left) = match kind {
+        SpinKind::Paid => {
+            let stake = (spin_cost * spins as u64) as f64;
+            let wager_id = stake_wager(bank.as_ref(), user_id, &game, stake, exposure, DEDUCT_MESSAGE, MR_HOUSE_ID).await?;
+            (wager_id, stake, None)
+        }
+        SpinKind::Free => {
+            ensure_house_can_cover(bank.as_ref(), MR_HOUSE_ID, exposure).await?;
+            match take_free_spins(user_id, &machine_name, spins, &game, MR_HOUSE_ID) {
+                Ok(Some((wager_id, taken, remaining))) => {
+                    fair_spins.truncate(taken as usize);
+                    (wager_id, 0.0, Some(remaining))
+                }
+                Ok(None) => return Err(Error::from("Those free spins have already been played.")),
+                Err(_) => return Err(Error::from("Sorry, I couldn't use up your free spins.")),
+            }
+        }
+    };
+
+    let (plays, pool_payouts, definition_hash) = {
+        let mut slot_machine = slot_machine.lock().unwrap();
+        let mut plays: Vec<(PlayResult, f64)> = fair_spins
+            .iter()
+            .map(|fair_spin| {
+                let jackpot_before = slot_machine.rolling_jackpot();
+                (slot_machine.play_seeded(fair_spin.seed, kind), jackpot_before)
+            })
+            .collect();
+        // The whole batch squares up with the pools at once, before anything is recorded, so a spin
+        // the pools couldn't take is dropped along with every spin after it.
+        let settlements: Vec<PoolSettlement> = plays
+            .iter()
+            .map(|(play_result, _)| pool_settlement(&progressive_jackpots, play_result, spin_cost))
+            .collect();
+        let pool_payouts = settle_jackpot_pools(&settlements).unwrap_or_else(|reason| {
+            error!("Couldn't settle progressive jackpots for wager {wager_id}: {reason:?}");
+            Vec::new()
+        });
+        if let Some((_, jackpot_before)) = plays.get(pool_payouts.len()) {
+            slot_machine.rewind_rolling_jackpot(*jackpot_before);
+            plays.truncate(pool_payouts.len());
+        }
+        // Saved under the machine's lock so concurrent spins can't persist jackpots out of order.
+        if let Err(reason) = save_rolling_jackpot(&machine_name, slot_machine.rolling_jackpot()) {
+            error!("Couldn't persist rolling jackpot for {machine_name}: {reason:?}");
+        }
+        (plays, pool_payouts, slot_machine.definition_hash().to_string())
     };
 
-    if get_libcoin_balance(user_id).await? < play_cost as f64 {
+    if plays.is_empty() {
+        // The pools are left as they were, so a pot these spins won is still there to be won.
+        call_off(bank.as_ref(), wager_id).await;
         return Err(Error::from(
-            "You don't have enough libcoin to play the slot machine!",
+            "Sorry, I couldn't square up the progressive jackpots, so these spins are called off and your stake is on its way back.",
         ));
     }
+    let called_off = (fair_spins.len() - plays.len()) as u32;
+    fair_spins.truncate(plays.len());
 
-    deduct_libcoin(user_id, play_cost as f64, DEDUCT_MESSAGE)
-        .await
-        .map_err(|_| Error::from("Sorry, looks like I'm having trouble contacting the bank."))?;
-    grant_libcoin(
-        MR_HOUSE_ID,
-        play_cost as f64,
-        &format!(
-            "Payment from {} playing the slot machine",
-            ctx.author().name
-        ),
-    )
-    .await
-    .map_err(|_| Error::from("Sorry, looks like I'm having trouble contacting the bank."))?;
+    let free_spin = kind == SpinKind::Free;
+    let guild_id = ctx.guild_id().map(|guild_id| guild_id.get());
+    let mut reports = Vec::with_capacity(plays.len());
+    let mut jackpot_hits = Vec::new();
+    for (((play_result, jackpot_before), fair_spin), pool_payout) in plays.into_iter().zip(fair_spins).zip(pool_payouts) {
+        // Everything the machine pays scales with the bet, its own jackpot included; progressive
+        // pools are fed in proportion to the stake but always pay out whatever they hold.
+        let machine_payout = play_result.payout as u64 * bet as u64;
+        if let Err(reason) = record_spin(wager_id, &machine_name, &definition_hash, &fair_spin, jackpot_before, machine_payout, free_spin, bet) {
+            error!("Couldn't record spin for wager {wager_id}: {reason:?}");
+        }
+        let progressive_wins: Vec<(String, u64)> = play_result
+            .progressive_hits
+            .iter()
+            .zip(pool_payout)
+            .map(|(&index, amount)| (progressive_jackpots[index].pool.title(), amount as u64))
+            .collect();
 
-    let play_result = {
-        let mut slot_machine = GORE_SLOT_MACHINE.lock().unwrap();
-        slot_machine.play()
-    };
+        // The machine's own jackpot is paid whole Libcoin at a time, like everything else.
+        let rolling_jackpot_hit = play_result
+            .is_jackpot
+            .then(|| ("Jackpot".to_string(), jackpot_before.floor() * bet as f64));
+        let progressive_hits = progressive_wins
+            .iter()
+            .map(|(title, amount)| (title.clone(), *amount as f64));
+        for (jackpot, amount) in rolling_jackpot_hit.into_iter().chain(progressive_hits) {
+            let hit = JackpotHit {
+                user_id,
+                guild_id,
+                machine_name: machine_name.clone(),
+                jackpot,
+                amount,
+                hit_at: Utc::now().to_rfc3339(),
+            };
+            if let Err(reason) = record_jackpot_hit(wager_id, &hit) {
+                error!("Couldn't record {} hit for wager {wager_id}: {reason:?}", hit.jackpot);
+            }
+            jackpot_hits.push(hit);
+        }
 
-    let embed = build_result_embed(&play_result);
+        reports.push(SpinReport {
+            payout: machine_payout + progressive_wins.iter().map(|(_, amount)| amount).sum::<u64>(),
+            play_result,
+            fair_spin,
+            progressive_wins,
+        });
+    }
 
-    ctx.send(CreateReply {
-        embeds: vec![embed],
-        ..Default::default()
+    // Free spins are always played at a bet of 1, so a bigger bet wins proportionally more of them.
+    // Free spins that were called off go back with them.
+    let free_spins_awarded: u32 = reports.iter().map(|report| report.play_result.free_spins_awarded * bet).sum();
+    let free_spins_returned = if free_spin { called_off } else { 0 };
+    if free_spins_awarded + free_spins_returned > 0 {
+        match award_free_spins(user_id, &machine_name, free_spins_awarded + free_spins_returned) {
+            Ok(remaining) => free_spins_left = Some(remaining),
+            Err(reason) => error!(
+                "Couldn't award {} free spins on {machine_name} to {user_id}: {reason:?}",
+                free_spins_awarded + free_spins_returned
+            ),
+        }
+    }
+
+    // Paid spins that were called off are refunded with the winnings of the ones that were played.
+    let refund = match kind {
+        SpinKind::Paid => spin_cost * called_off as u64,
+        SpinKind::Free => 0,
+    };
+    let payout: u64 = reports.iter().map(|report| report.payout).sum();
+    let payout_status = settle(bank.as_ref(), wager_id, user_id, (payout + refund) as f64, GRANT_MESSAGE).await?;
+
+    Ok(SpinBatch {
+        machine_name,
+        kind,
+        bet,
+        stake: stake - refund as f64,
+        spins: reports,
+        payout,
+        payout_status,
+        free_spins_awarded,
+        free_spins_left,
+        called_off,
+        jackpot_hits,
     })
-    .await?;
+}
 
-    grant_libcoin(user_id, play_result.payout as f64, GRANT_MESSAGE).await
-        .map_err(|_| Error::from("Well this is embarassing. I wanted to give you your winnings but it looks like I'm having trouble contacting the bank."))?;
+/// What a spin feeds into the machine's pools, paid spins only, and the pools it won.
+fn pool_settlement<'a>(
+    progressive_jackpots: &'a [ProgressiveJackpot],
+    play_result: &PlayResult,
+    spin_cost: u64,
+) -> PoolSettlement<'a> {
+    let contributions = match play_result.kind {
+        SpinKind::Paid => progressive_jackpots
+            .iter()
+            .map(|progressive| (progressive.pool.name.as_str(), spin_cost as f64 * progressive.contribution_rate))
+            .filter(|(_, amount)| *amount > 0.0)
+            .collect(),
+        SpinKind::Free => Vec::new(),
+    };
+    let wins = play_result
+        .progressive_hits
+        .iter()
+        .map(|&index| (progressive_jackpots[index].pool.name.as_str(), progressive_jackpots[index].pool.seed))
+        .collect();
 
-    Ok(())
+    PoolSettlement { contributions, wins }
 }
 
 #[poise::command(
@@ -74,9 +436,16 @@ pub async fn slots(ctx: Context<'_>) -> Result<(), Error> {
     description_localized("fr", "Consultez la table des gains de la machine à sous."),
     description_localized("es-ES", "Mira la lista de pagos de la ruleta.")
 )]
-pub async fn paytable(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn paytable(
+    ctx: Context<'_>,
+    #[description = "Which slot machine's paytable to show"]
+    #[description_localized("fr", "La machine à sous dont afficher la table des gains")]
+    #[description_localized("es-ES", "La tragaperras cuya lista de pagos quieres ver")]
+    #[autocomplete = "autocomplete_machine"]
+    machine: Option<String>,
+) -> Result<(), Error> {
     let embed = {
-        let slot_machine = GORE_SLOT_MACHINE.lock().unwrap();
+        let slot_machine = ctx.data().slot_machines.get(machine.as_deref())?.lock().unwrap();
         slot_machine.get_pay_table_embed()
     };
 
@@ -89,6 +458,72 @@ pub async fn paytable(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "See how much is in each progressive jackpot and which machines can win it."),
+    description_localized("fr", "Consultez les jackpots progressifs et les machines qui peuvent les remporter."),
+    description_localized("es-ES", "Mira cuánto hay en cada bote progresivo y qué máquinas pueden ganarlo.")
+)]
+pub async fn jackpots(ctx: Context<'_>) -> Result<(), Error> {
+    let pool_values = jackpot_pool_values()
+        .map_err(|_| Error::from("Sorry, I couldn't check the progressive jackpots."))?;
+    let registry = &ctx.data().slot_machines;
+
+    let fields: Vec<(String, String, bool)> = registry
+        .pools()
+        .iter()
+        .map(|pool| {
+            let value = pool_values.get(&pool.name).copied().unwrap_or(pool.seed as f64);
+            let feeders: Vec<String> = registry
+                .iter()
+                .filter_map(|(name, slot_machine)| {
+                    let slot_machine = slot_machine.lock().unwrap();
+                    let index = slot_machine
+                        .progressive_jackpots()
+                        .iter()
+                        .position(|progressive| progressive.pool.name == pool.name)?;
+                    let probability = slot_machine.analysis().progressive_hit_probabilities[index];
+                    let odds = if probability > 0.0 {
+                        format!("won 1 in {:.0} spins", 1.0 / probability)
+                    } else {
+                        "can't be won there".to_string()
+                    };
+                    Some(format!(
+                        "{}: {}% of each spin, {}",
+                        name,
+                        slot_machine.progressive_jackpots()[index].contribution_rate * 100.0,
+                        odds
+                    ))
+                })
+                .collect();
+            let feeders = if feeders.is_empty() {
+                "No machine feeds this pool yet.".to_string()
+            } else {
+                feeders.join("\n")
+            };
+            (
+                pool.title(),
+                format!("**{:.2} Libcoin** (starts from {})\n{}", value, pool.seed, feeders),
+                false,
+            )
+        })
+        .collect();
+
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title("💰 Progressive Jackpots")
+        .description("Every machine that feeds a pool plays for the same pot. When it's won, it starts again from its seed.")
+        .fields(fields);
+
+    ctx.send(CreateReply {
+        embeds: vec![embed],
+        ..Default::default()
+    })
+    .await?;
+
+    Ok(())
+}
+
 #[poise::command(
     slash_command,
     description_localized(
@@ -105,7 +540,10 @@ pub async fn paytable(ctx: Context<'_>) -> Result<(), Error> {
     )
 )]
 pub async fn stats(ctx: Context<'_>) -> Result<(), Error> {
-    let transactions = get_user_transactions(ctx.author().id.get())
+    let transactions = ctx
+        .data()
+        .bank
+        .get_user_transactions(ctx.author().id.get())
         .await
         .map_err(|_| Error::from("Sorry, looks like I'm having trouble contacting the bank."))?;
 
@@ -146,32 +584,158 @@ pub async fn stats(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-fn build_result_embed(play_result: &PlayResult) -> CreateEmbed {
-    let symbols: String = play_result
-        .symbols
+fn build_result_embed(batch: &SpinBatch) -> CreateEmbed {
+    let is_jackpot = batch
+        .spins
         .iter()
-        .map(|s| s.to_string())
-        .collect::<Vec<_>>()
-        .join("");
-    let footer_message = if play_result.payout >= 500 {
+        .any(|report| report.play_result.is_jackpot || !report.progressive_wins.is_empty());
+    let footer_message = if is_jackpot {
         "🎉 Jackpot! 🎉".to_string()
-    } else if play_result.payout > 0 {
+    } else if batch.payout > 0 {
         format!(
             "You won! Maybe next time you'll hit the jackpot!\nCurrent Jackpot:{}",
-            play_result.current_jackpot_value
+            batch.spins.last().map_or(0.0, |report| report.play_result.current_jackpot_value)
         )
     } else {
         "Better luck next time!".to_string()
     };
+    let payout = describe_payout(batch.payout, batch.payout_status);
 
-    let embed = CreateEmbed::new()
+    let mut embed = match batch.spins.as_slice() {
+        [report] => single_spin_embed(report, batch.bet, &footer_message),
+        reports => batch_embed(batch, reports, &footer_message),
+    };
+
+    if batch.bet > 1 {
+        embed = embed.field("Bet", format!("x{} ({} Libcoin a spin)", batch.bet, batch.stake / batch.spins.len() as f64), true);
+    }
+    embed = embed.field("Payout", payout, true);
+
+    if batch.free_spins_awarded > 0 {
+        embed = embed.field(
+            "🎁 Bonus!",
+            format!("You won {} free spins! Use /slots to play them.", batch.free_spins_awarded),
+            false,
+        );
+    }
+    if batch.called_off > 0 {
+        let handed_back = match batch.kind {
+            SpinKind::Paid => "their stake is on its way back",
+            SpinKind::Free => "they're back with your free spins",
+        };
+        embed = embed.field(
+            "Called Off",
+            format!("I couldn't square up the progressive jackpots for {} of your spins, so {}.", batch.called_off, handed_back),
+            false,
+        );
+    }
+    match batch.free_spins_left {
+        Some(remaining) if remaining > 0 => embed.field("Free Spins Left", remaining.to_string(), true),
+        Some(_) => embed.field("Free Spins Left", "That was your last one!", true),
+        None => embed,
+    }
+}
+
+fn single_spin_embed(report: &SpinReport, bet: u32, footer_message: &str) -> CreateEmbed {
+    let play_result = &report.play_result;
+    let grid: String = play_result
+        .window
+        .iter()
+        .map(|row| row.join(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut embed = CreateEmbed::new()
         .color(0x5b9e48)
-        .title("🎰 Slot Machine Results")
-        .footer(CreateEmbedFooter::new(footer_message))
-        .fields([
-            ("Spin Result", symbols, false),
-            ("Payout", play_result.payout.to_string(), true),
-        ]);
+        .title(match play_result.kind {
+            SpinKind::Paid => "🎰 Slot Machine Results",
+            SpinKind::Free => "🎁 Free Spin Results",
+        })
+        .footer(CreateEmbedFooter::new(format!(
+            "{}\nSeed: {} | Nonce: {}",
+            footer_message,
+            format_seed(play_result.seed),
+            report.fair_spin.nonce
+        )))
+        .field("Spin Result", grid, false);
+
+    if !play_result.wins.is_empty() {
+        let wins: Vec<String> = play_result
+            .wins
+            .iter()
+            .map(|win| {
+                let source = match win.line {
+                    Some(line) => format!("Line {}", line + 1),
+                    None => "Scatter".to_string(),
+                };
+                let payout = win.payout as u64 * bet as u64;
+                if win.multiplier > 1 {
+                    format!("{}: {} (x{})", source, payout, win.multiplier)
+                } else {
+                    format!("{}: {}", source, payout)
+                }
+            })
+            .collect();
+        embed = embed.field("Winning Lines", wins.join("\n"), true);
+    }
+
+    if !report.progressive_wins.is_empty() {
+        embed = embed.field("🏆 Progressive Jackpot!", describe_progressive_wins(&report.progressive_wins), true);
+    }
 
     embed
 }
+
+/// One line per spin showing its middle row, which keeps ten spins of custom emoji well inside
+/// Discord's embed size limit.
+fn batch_embed(batch: &SpinBatch, reports: &[SpinReport], footer_message: &str) -> CreateEmbed {
+    let spin_lines: Vec<String> = reports
+        .iter()
+        .enumerate()
+        .map(|(index, report)| {
+            let window = &report.play_result.window;
+            let middle_row = window[window.len() / 2].join("");
+            let jackpot = if report.play_result.is_jackpot || !report.progressive_wins.is_empty() {
+                " 🎉"
+            } else {
+                ""
+            };
+            format!("`#{}` {} **{}**{}", index + 1, middle_row, report.payout, jackpot)
+        })
+        .collect();
+    let seeds: Vec<String> = reports.iter().map(|report| format_seed(report.play_result.seed)).collect();
+    let progressive_wins: Vec<(String, u64)> = reports
+        .iter()
+        .flat_map(|report| report.progressive_wins.iter().cloned())
+        .collect();
+
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(match batch.kind {
+            SpinKind::Paid => format!("🎰 Slot Machine Results ({} spins)", reports.len()),
+            SpinKind::Free => format!("🎁 Free Spin Results ({} spins)", reports.len()),
+        })
+        .description(spin_lines.join("\n"))
+        .footer(CreateEmbedFooter::new(format!(
+            "{}\nSeeds: {} | Nonces: {}-{}",
+            footer_message,
+            seeds.join(", "),
+            reports[0].fair_spin.nonce,
+            reports[reports.len() - 1].fair_spin.nonce
+        )))
+        .field("Staked", batch.stake.to_string(), true);
+
+    if progressive_wins.is_empty() {
+        embed
+    } else {
+        embed.field("🏆 Progressive Jackpot!", describe_progressive_wins(&progressive_wins), true)
+    }
+}
+
+fn describe_progressive_wins(progressive_wins: &[(String, u64)]) -> String {
+    progressive_wins
+        .iter()
+        .map(|(title, amount)| format!("{}: {}", title, amount))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,5 @@
+pub mod table;
+#[allow(clippy::module_inception)]
+pub mod video_poker;
+
+pub use video_poker::*;
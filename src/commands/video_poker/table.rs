@@ -0,0 +1,172 @@
+use super::{Paytable, PayRule, HAND_SIZE, MAX_BET};
+use crate::commands::buttons::wait_for_press;
+use crate::commands::cards::{format_cards, Card, Shoe};
+use crate::services::settlement::{call_off, describe_payout, settle, stake_wager, PayoutStatus};
+use crate::{Context, Error, MR_HOUSE_ID};
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use serenity::builder::{CreateActionRow, CreateButton, CreateEmbed};
+use std::time::Duration;
+use tracing::error;
+
+const VIDEO_POKER_GAME: &str = "videopoker";
+const DEDUCT_MESSAGE: &str = "Playing video poker";
+const GRANT_MESSAGE: &str = "Winning at video poker";
+// Left alone this long, the hand is drawn with whatever's held.
+const DRAW_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Play a hand of video poker: hold the cards you like and draw the rest."),
+    description_localized("fr", "Jouez une main de vidéo poker : gardez les cartes qui vous plaisent et tirez les autres."),
+    description_localized("es-ES", "Juega una mano de videopóquer: guarda las cartas que quieras y cambia el resto.")
+)]
+pub async fn videopoker(
+    ctx: Context<'_>,
+    #[description = "How much Libcoin to bet"]
+    #[description_localized("fr", "Combien de Libcoin miser")]
+    #[description_localized("es-ES", "Cuántas Libcoin apostar")]
+    #[min = 1]
+    bet: u32,
+    #[description = "Which game's paytable to play (Jacks or Better by default)"]
+    #[description_localized("fr", "La table des gains à jouer (Jacks or Better par défaut)")]
+    #[description_localized("es-ES", "Qué tabla de pagos jugar (Jacks or Better por defecto)")]
+    paytable: Option<Paytable>,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+    let bank = &ctx.data().bank;
+    let paytable = paytable.unwrap_or(Paytable::JacksOrBetter);
+
+    if bet > MAX_BET {
+        return Err(Error::from(format!("The machine takes up to {} Libcoin a hand.", MAX_BET)));
+    }
+    let wager_id = stake_wager(
+        bank.as_ref(),
+        user_id,
+        VIDEO_POKER_GAME,
+        bet as f64,
+        paytable.max_pays() as f64 * bet as f64,
+        DEDUCT_MESSAGE,
+        MR_HOUSE_ID,
+    )
+    .await?;
+
+    let mut deck = Shoe::new(1);
+    let mut cards: Vec<Card> = (0..HAND_SIZE).map(|_| deck.draw()).collect();
+    let mut held = [false; HAND_SIZE];
+
+    let id_prefix = format!("{}-videopoker-", ctx.id());
+    let draw_id = format!("{}draw", id_prefix);
+    let hold_ids: Vec<String> = (0..HAND_SIZE).map(|index| format!("{}hold-{}", id_prefix, index)).collect();
+    let mut custom_ids = hold_ids.clone();
+    custom_ids.push(draw_id.clone());
+
+    let deal = CreateReply {
+        embeds: vec![hand_embed(paytable, bet, &cards, &held, None), paytable.get_pay_table_embed(bet)],
+        components: Some(hold_buttons(&cards, &held, &hold_ids, &draw_id)),
+        ..Default::default()
+    };
+    let reply = match ctx.send(deal).await {
+        Ok(reply) => reply,
+        Err(reason) => {
+            call_off(bank.as_ref(), wager_id).await;
+            return Err(reason.into());
+        }
+    };
+
+    while let Some(pressed) = wait_for_press(ctx, &custom_ids, DRAW_TIMEOUT).await {
+        let Some(index) = hold_ids.iter().position(|hold_id| *hold_id == pressed) else {
+            break;
+        };
+        held[index] = !held[index];
+        let update = CreateReply {
+            embeds: vec![hand_embed(paytable, bet, &cards, &held, None), paytable.get_pay_table_embed(bet)],
+            components: Some(hold_buttons(&cards, &held, &hold_ids, &draw_id)),
+            ..Default::default()
+        };
+        if let Err(reason) = reply.edit(ctx, update).await {
+            error!("Couldn't update the video poker hand: {reason:?}");
+        }
+    }
+
+    for (card, held) in cards.iter_mut().zip(held) {
+        if !held {
+            *card = deck.draw();
+        }
+    }
+    let winning_rule = paytable.evaluate(&cards);
+    let payout = winning_rule.map_or(0, |rule| rule.payout(bet));
+    let payout_status = settle(bank.as_ref(), wager_id, user_id, payout as f64, GRANT_MESSAGE).await?;
+
+    reply
+        .edit(
+            ctx,
+            CreateReply {
+                embeds: vec![hand_embed(paytable, bet, &cards, &held, Some((winning_rule, payout, payout_status)))],
+                components: Some(Vec::new()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn hold_buttons(cards: &[Card], held: &[bool], hold_ids: &[String], draw_id: &str) -> Vec<CreateActionRow> {
+    let holds = cards
+        .iter()
+        .zip(held)
+        .zip(hold_ids)
+        .map(|((card, held), hold_id)| {
+            let (label, style) = if *held {
+                (format!("{} held", card), serenity::ButtonStyle::Success)
+            } else {
+                (card.to_string(), serenity::ButtonStyle::Secondary)
+            };
+            CreateButton::new(hold_id).label(label).style(style)
+        })
+        .collect();
+    let draw = CreateButton::new(draw_id).label("Draw").style(serenity::ButtonStyle::Primary);
+
+    vec![CreateActionRow::Buttons(holds), CreateActionRow::Buttons(vec![draw])]
+}
+
+fn hand_embed(
+    paytable: Paytable,
+    bet: u32,
+    cards: &[Card],
+    held: &[bool],
+    result: Option<(Option<&PayRule>, u64, PayoutStatus)>,
+) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .color(0x5b9e48)
+        .title(format!("🃏 {}", paytable.title()))
+        .field("Hand", format_cards(cards), false)
+        .field("Bet", bet.to_string(), true);
+
+    match result {
+        None => {
+            let holding: Vec<Card> = cards.iter().zip(held).filter(|(_, held)| **held).map(|(card, _)| *card).collect();
+            let holding = if holding.is_empty() {
+                "nothing yet".to_string()
+            } else {
+                format_cards(&holding)
+            };
+            let dealt = match paytable.evaluate(cards) {
+                Some(rule) => format!("Dealt: **{}**. ", rule.name),
+                None => String::new(),
+            };
+            embed.description(format!(
+                "{}Tap the cards to hold, then draw. Holding {}.",
+                dealt, holding
+            ))
+        }
+        Some((winning_rule, payout, payout_status)) => {
+            let description = match winning_rule {
+                Some(rule) => format!("**{}!** Pays {} for 1.", rule.name, rule.pays),
+                None => "No win this time.".to_string(),
+            };
+            embed.description(description).field("Payout", describe_payout(payout, payout_status), true)
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use crate::commands::cards::{Card, Rank};
+use poise::serenity_prelude as serenity;
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+
+pub const HAND_SIZE: usize = 5;
+/// Most Libcoin one hand can be played for, so a royal on it stays well inside `u64`.
+pub const MAX_BET: u32 = 100_000;
+
+/// A hand a paytable can pay on. Deuces only count as wild on paytables that say so.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandRank {
+    RoyalFlush,
+    /// A royal flush made without any wild cards.
+    NaturalRoyalFlush,
+    FourDeuces,
+    FiveOfAKind,
+    StraightFlush,
+    FourOfAKind,
+    FourAces,
+    FourTwosThroughFours,
+    FourFivesThroughKings,
+    FullHouse,
+    Flush,
+    Straight,
+    ThreeOfAKind,
+    TwoPair,
+    JacksOrBetter,
+}
+
+pub struct PayRule {
+    pub hand: HandRank,
+    pub name: &'static str,
+    /// Returned per Libcoin bet, the bet included.
+    pub pays: u32,
+}
+
+impl PayRule {
+    /// What this returns on a `bet` Libcoin hand, the bet included.
+    pub fn payout(&self, bet: u32) -> u64 {
+        self.pays as u64 * bet as u64
+    }
+}
+
+const fn rule(hand: HandRank, name: &'static str, pays: u32) -> PayRule {
+    PayRule { hand, name, pays }
+}
+
+// Full-pay 9/6 Jacks or Better, with the royal paid at the max-coin rate.
+const JACKS_OR_BETTER: [PayRule; 9] = [
+    rule(HandRank::RoyalFlush, "Royal Flush", 800),
+    rule(HandRank::StraightFlush, "Straight Flush", 50),
+    rule(HandRank::FourOfAKind, "Four of a Kind", 25),
+    rule(HandRank::FullHouse, "Full House", 9),
+    rule(HandRank::Flush, "Flush", 6),
+    rule(HandRank::Straight, "Straight", 4),
+    rule(HandRank::ThreeOfAKind, "Three of a Kind", 3),
+    rule(HandRank::TwoPair, "Two Pair", 2),
+    rule(HandRank::JacksOrBetter, "Jacks or Better", 1),
+];
+
+// "Not So Ugly" Deuces Wild; full pay would hand the house a losing game.
+const DEUCES_WILD: [PayRule; 10] = [
+    rule(HandRank::NaturalRoyalFlush, "Natural Royal Flush", 800),
+    rule(HandRank::FourDeuces, "Four Deuces", 200),
+    rule(HandRank::RoyalFlush, "Wild Royal Flush", 25),
+    rule(HandRank::FiveOfAKind, "Five of a Kind", 16),
+    rule(HandRank::StraightFlush, "Straight Flush", 10),
+    rule(HandRank::FourOfAKind, "Four of a Kind", 4),
+    rule(HandRank::FullHouse, "Full House", 4),
+    rule(HandRank::Flush, "Flush", 3),
+    rule(HandRank::Straight, "Straight", 2),
+    rule(HandRank::ThreeOfAKind, "Three of a Kind", 1),
+];
+
+// 8/5 Bonus Poker.
+const BONUS_POKER: [PayRule; 11] = [
+    rule(HandRank::RoyalFlush, "Royal Flush", 800),
+    rule(HandRank::StraightFlush, "Straight Flush", 50),
+    rule(HandRank::FourAces, "Four Aces", 80),
+    rule(HandRank::FourTwosThroughFours, "Four 2s-4s", 40),
+    rule(HandRank::FourFivesThroughKings, "Four 5s-Ks", 25),
+    rule(HandRank::FullHouse, "Full House", 8),
+    rule(HandRank::Flush, "Flush", 5),
+    rule(HandRank::Straight, "Straight", 4),
+    rule(HandRank::ThreeOfAKind, "Three of a Kind", 3),
+    rule(HandRank::TwoPair, "Two Pair", 2),
+    rule(HandRank::JacksOrBetter, "Jacks or Better", 1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum Paytable {
+    #[name = "Jacks or Better"]
+    JacksOrBetter,
+    #[name = "Deuces Wild"]
+    DeucesWild,
+    #[name = "Bonus Poker"]
+    BonusPoker,
+}
+
+impl Paytable {
+    pub fn title(self) -> &'static str {
+        match self {
+            Paytable::JacksOrBetter => "Jacks or Better",
+            Paytable::DeucesWild => "Deuces Wild",
+            Paytable::BonusPoker => "Bonus Poker",
+        }
+    }
+
+    /// Best hand first; a hand is paid on the first rule it makes.
+    pub fn rules(self) -> &'static [PayRule] {
+        match self {
+            Paytable::JacksOrBetter => &JACKS_OR_BETTER,
+            Paytable::DeucesWild => &DEUCES_WILD,
+            Paytable::BonusPoker => &BONUS_POKER,
+        }
+    }
+
+    pub fn wild_deuces(self) -> bool {
+        self == Paytable::DeucesWild
+    }
+
+    /// The long-run return with perfect strategy, as published for these pays.
+    pub fn perfect_play_rtp(self) -> f64 {
+        match self {
+            Paytable::JacksOrBetter => 0.9954,
+            Paytable::DeucesWild => 0.9973,
+            Paytable::BonusPoker => 0.9917,
+        }
+    }
+
+    pub fn max_pays(self) -> u32 {
+        self.rules().iter().map(|rule| rule.pays).max().unwrap_or(0)
+    }
+
+    pub fn evaluate(self, cards: &[Card]) -> Option<&'static PayRule> {
+        let shape = HandShape::new(cards, self.wild_deuces());
+        self.rules().iter().find(|rule| shape.makes(rule.hand))
+    }
+
+    pub fn get_pay_table_embed(self, bet: u32) -> CreateEmbed {
+        let mut footer = format!(
+            "Pays shown for 1 Libcoin, your bet included\nRTP with perfect strategy: {:.2}%",
+            self.perfect_play_rtp() * 100.0
+        );
+        if self.wild_deuces() {
+            footer.push_str(" | Deuces are wild");
+        }
+
+        CreateEmbed::new()
+            .title(format!("Pay Table ({}) - {} Libcoin a Hand", self.title(), bet))
+            .color(0x5b9e48)
+            .footer(CreateEmbedFooter::new(footer))
+            .fields(self.rules().iter().map(|rule| (rule.name, format!("{} for 1", rule.pays), true)))
+    }
+}
+
+/// What a five-card hand has to work with: its natural cards grouped by rank, and how many wild
+/// cards can stand in for whatever's missing.
+struct HandShape {
+    /// (rank, how many) for the natural cards, most common first.
+    rank_counts: Vec<(Rank, usize)>,
+    wilds: usize,
+    flush: bool,
+    straight: bool,
+    royal: bool,
+}
+
+impl HandShape {
+    fn new(cards: &[Card], wild_deuces: bool) -> Self {
+        let (wild, natural): (Vec<&Card>, Vec<&Card>) =
+            cards.iter().partition(|card| wild_deuces && card.rank == Rank::Two);
+
+        let mut rank_counts: Vec<(Rank, usize)> = Vec::new();
+        for card in &natural {
+            match rank_counts.iter_mut().find(|(rank, _)| *rank == card.rank) {
+                Some((_, count)) => *count += 1,
+                None => rank_counts.push((card.rank, 1)),
+            }
+        }
+        rank_counts.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+
+        let flush = natural.windows(2).all(|pair| pair[0].suit == pair[1].suit);
+        // Wild cards can fill any gaps, as long as the natural cards fit inside five in a row.
+        let distinct = rank_counts.len() == natural.len();
+        let fits = |low: u8| {
+            natural.iter().all(|card| {
+                let value = match (card.rank, low) {
+                    (Rank::Ace, 1) => 1,
+                    (rank, _) => rank.value(),
+                };
+                (low..low + 5).contains(&value)
+            })
+        };
+        let straight = distinct && (1..=10).any(fits);
+        let royal = distinct && fits(10);
+
+        HandShape {
+            rank_counts,
+            wilds: wild.len(),
+            flush,
+            straight,
+            royal,
+        }
+    }
+
+    fn count(&self, index: usize) -> usize {
+        self.rank_counts.get(index).map_or(0, |(_, count)| *count)
+    }
+
+    /// The rank a four (or better) of a kind is made of, if there is one.
+    fn quads(&self) -> Option<Rank> {
+        match self.rank_counts.first() {
+            Some((rank, count)) if count + self.wilds >= 4 => Some(*rank),
+            _ => None,
+        }
+    }
+
+    fn makes(&self, hand: HandRank) -> bool {
+        match hand {
+            HandRank::RoyalFlush => self.royal && self.flush,
+            HandRank::NaturalRoyalFlush => self.royal && self.flush && self.wilds == 0,
+            HandRank::FourDeuces => self.wilds == 4,
+            HandRank::FiveOfAKind => self.count(0) + self.wilds >= 5,
+            HandRank::StraightFlush => self.straight && self.flush,
+            HandRank::FourOfAKind => self.quads().is_some(),
+            HandRank::FourAces => self.quads() == Some(Rank::Ace),
+            HandRank::FourTwosThroughFours => self.quads().is_some_and(|rank| rank <= Rank::Four),
+            HandRank::FourFivesThroughKings => {
+                self.quads().is_some_and(|rank| (Rank::Five..=Rank::King).contains(&rank))
+            }
+            // With wilds, any two ranks make a full house once the hand is past four of a kind.
+            HandRank::FullHouse => self.rank_counts.len() <= 2,
+            HandRank::Flush => self.flush,
+            HandRank::Straight => self.straight,
+            HandRank::ThreeOfAKind => self.count(0) + self.wilds >= 3,
+            HandRank::TwoPair => self.count(0) >= 2 && self.count(1) >= 2,
+            HandRank::JacksOrBetter => self
+                .rank_counts
+                .iter()
+                .any(|(rank, count)| *rank >= Rank::Jack && count + self.wilds >= 2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::cards::Suit;
+
+    /// Cards written rank then suit, e.g. "As Td 2c".
+    fn hand(text: &str) -> Vec<Card> {
+        text.split_whitespace()
+            .map(|card| {
+                let (rank, suit) = card.split_at(1);
+                let rank = match rank {
+                    "T" => Rank::Ten,
+                    "J" => Rank::Jack,
+                    "Q" => Rank::Queen,
+                    "K" => Rank::King,
+                    "A" => Rank::Ace,
+                    digit => Rank::ALL[digit.parse::<usize>().unwrap() - 2],
+                };
+                let suit = match suit {
+                    "s" => Suit::Spades,
+                    "h" => Suit::Hearts,
+                    "d" => Suit::Diamonds,
+                    _ => Suit::Clubs,
+                };
+                Card { rank, suit }
+            })
+            .collect()
+    }
+
+    fn pays(paytable: Paytable, cards: &str) -> Option<&'static str> {
+        paytable.evaluate(&hand(cards)).map(|rule| rule.name)
+    }
+
+    fn paytable_rule(paytable: Paytable, hand: HandRank) -> &'static PayRule {
+        paytable.rules().iter().find(|rule| rule.hand == hand).unwrap()
+    }
+
+    #[test]
+    fn jacks_or_better_pays_its_hands() {
+        let table = Paytable::JacksOrBetter;
+        assert_eq!(pays(table, "As Ks Qs Js Ts"), Some("Royal Flush"));
+        assert_eq!(pays(table, "Ah 2h 3h 4h 5h"), Some("Straight Flush"));
+        assert_eq!(pays(table, "9c 9d 9h 9s 2c"), Some("Four of a Kind"));
+        assert_eq!(pays(table, "Kc Kd Ks 4h 4d"), Some("Full House"));
+        assert_eq!(pays(table, "2h 7h 9h Jh Kh"), Some("Flush"));
+        assert_eq!(pays(table, "Ac 2d 3h 4s 5c"), Some("Straight"));
+        assert_eq!(pays(table, "Tc Jd Qh Ks Ac"), Some("Straight"));
+        assert_eq!(pays(table, "7s 7d 7h Kc 2d"), Some("Three of a Kind"));
+        assert_eq!(pays(table, "7s 7d Kh Kc 2d"), Some("Two Pair"));
+        assert_eq!(pays(table, "Js Jd 3h 5c 9d"), Some("Jacks or Better"));
+        assert_eq!(pays(table, "Ts Td 3h 5c 9d"), None);
+        // Straights don't wrap round the ace, and deuces are just deuces here.
+        assert_eq!(pays(table, "Qc Kd Ah 2s 3c"), None);
+        assert_eq!(pays(table, "2c 2d 2h 2s 9c"), Some("Four of a Kind"));
+    }
+
+    #[test]
+    fn deuces_wild_fills_in_with_deuces() {
+        let table = Paytable::DeucesWild;
+        assert_eq!(pays(table, "As Ks Qs Js Ts"), Some("Natural Royal Flush"));
+        assert_eq!(pays(table, "2c 2d 2h 2s 9c"), Some("Four Deuces"));
+        assert_eq!(pays(table, "As Ks 2d Js Ts"), Some("Wild Royal Flush"));
+        assert_eq!(pays(table, "7s 7d 7h 2c 2d"), Some("Five of a Kind"));
+        assert_eq!(pays(table, "2c 5h 6h 8h 9h"), Some("Straight Flush"));
+        assert_eq!(pays(table, "2c 2d 9h 9s 4c"), Some("Four of a Kind"));
+        assert_eq!(pays(table, "2c 9h 9s 4c 4d"), Some("Full House"));
+        assert_eq!(pays(table, "2c 3h 8h Jh Kh"), Some("Flush"));
+        assert_eq!(pays(table, "2c 5d 6h 8s 9c"), Some("Straight"));
+        assert_eq!(pays(table, "2c 9h 9s 4c Jd"), Some("Three of a Kind"));
+        // Pairs don't pay on deuces wild, not even two of them.
+        assert_eq!(pays(table, "Kh Kd 5s 5c 9d"), None);
+        assert_eq!(pays(table, "Js Jd 3h 5c 9d"), None);
+    }
+
+    #[test]
+    fn bonus_poker_pays_more_for_low_and_ace_quads() {
+        let table = Paytable::BonusPoker;
+        assert_eq!(pays(table, "Ac Ad Ah As 9c"), Some("Four Aces"));
+        assert_eq!(pays(table, "3c 3d 3h 3s 9c"), Some("Four 2s-4s"));
+        assert_eq!(pays(table, "9c 9d 9h 9s 2c"), Some("Four 5s-Ks"));
+        assert_eq!(pays(table, "Kc Kd Kh Ks Ac"), Some("Four 5s-Ks"));
+        assert_eq!(pays(table, "Kc Kd Ks 4h 4d"), Some("Full House"));
+        assert_eq!(pays(table, "Qs Qd 3h 5c 9d"), Some("Jacks or Better"));
+    }
+
+    #[test]
+    fn deuces_only_stand_in_when_the_paytable_says_so() {
+        let shape = HandShape::new(&hand("9h 2c 4c 9s 4d"), true);
+        assert!(shape.makes(HandRank::FullHouse));
+        assert!(shape.makes(HandRank::ThreeOfAKind));
+        assert!(!shape.makes(HandRank::FourOfAKind));
+        assert!(!shape.makes(HandRank::Straight));
+
+        let shape = HandShape::new(&hand("9h 2c 4c 9s 4d"), false);
+        assert!(shape.makes(HandRank::TwoPair));
+        assert!(!shape.makes(HandRank::FullHouse));
+    }
+
+    #[test]
+    fn the_royal_scales_with_the_bet() {
+        for paytable in [Paytable::JacksOrBetter, Paytable::DeucesWild, Paytable::BonusPoker] {
+            assert_eq!(paytable.max_pays(), 800);
+        }
+        let royal = paytable_rule(Paytable::JacksOrBetter, HandRank::RoyalFlush);
+        assert_eq!(royal.payout(MAX_BET), 80_000_000);
+        assert_eq!(royal.payout(u32::MAX), 800 * u32::MAX as u64);
+    }
+}
@@ -0,0 +1,97 @@
+pub mod commands;
+pub mod services;
+
+use once_cell::sync::Lazy;
+use commands::cards::ShoeRack;
+use commands::roulette::multiplayer::RouletteRound;
+use commands::sessions::ChannelSessions;
+use commands::slot_machine::MachineRegistry;
+use services::libcoin::LibcoinBank;
+use std::sync::Arc;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub type Context<'a> = poise::Context<'a, Data, Error>;
+
+pub mod built_info {
+    include!(concat!(env!("OUT_DIR"), "/built.rs"));
+}
+
+// Mr. House's own Libcoin account: every game's stakes go into it and every payout comes out of it.
+pub const MR_HOUSE_ID: u64 = 1382600478206066769;
+
+pub static PANOPTICON_TOKEN: Lazy<String> = Lazy::new(|| {
+    std::env::var("PANOPTICON_TOKEN")
+        .expect("Expected PANOPTICON_TOKEN environment variable")
+});
+
+pub static PANOPTICON_URL: Lazy<String> = Lazy::new(|| {
+    std::env::var("PANOPTICON_URL").unwrap_or_else(|_| "https://panopticon.cacheblasters.com".to_string())
+});
+
+// "panopticon" (default) talks to the real bank, "memory" keeps a throwaway ledger for local development.
+pub static LIBCOIN_BANK: Lazy<String> = Lazy::new(|| {
+    std::env::var("LIBCOIN_BANK").unwrap_or_else(|_| "panopticon".to_string())
+});
+
+pub static IN_MEMORY_STARTING_BALANCE: Lazy<f64> = Lazy::new(|| {
+    std::env::var("IN_MEMORY_STARTING_BALANCE")
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .unwrap_or(1000.0)
+});
+
+pub static HOUSE_BANKROLL_FLOOR: Lazy<f64> = Lazy::new(|| {
+    std::env::var("HOUSE_BANKROLL_FLOOR")
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .unwrap_or(0.0)
+});
+
+// Directory of .toml/.json slot machine definitions, loaded alongside the built-in gore machine.
+pub static SLOT_MACHINES_DIR: Lazy<String> = Lazy::new(|| {
+    std::env::var("SLOT_MACHINES_DIR").unwrap_or_else(|_| "machines".to_string())
+});
+
+// Progressive jackpot pools machines can feed. The built-in mini/major/grand tiers are used when the file is missing.
+pub static JACKPOT_POOLS_FILE: Lazy<String> = Lazy::new(|| {
+    std::env::var("JACKPOT_POOLS_FILE").unwrap_or_else(|_| "jackpot_pools.toml".to_string())
+});
+
+pub static BLACKJACK_DECKS: Lazy<u32> = Lazy::new(|| {
+    std::env::var("BLACKJACK_DECKS")
+        .ok()
+        .and_then(|s| s.parse::<u32>().ok())
+        .filter(|decks| *decks > 0)
+        .unwrap_or(6)
+});
+
+// Off (the default) means the dealer stands on every 17, soft or not.
+pub static BLACKJACK_DEALER_HITS_SOFT_17: Lazy<bool> = Lazy::new(|| {
+    std::env::var("BLACKJACK_DEALER_HITS_SOFT_17")
+        .ok()
+        .and_then(|s| s.parse::<bool>().ok())
+        .unwrap_or(false)
+});
+
+// Share of a duel's pot the house keeps, as a percentage. The winner takes the rest.
+pub static DUEL_RAKE_PERCENT: Lazy<f64> = Lazy::new(|| {
+    std::env::var("DUEL_RAKE_PERCENT")
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|percent| (0.0..=100.0).contains(percent))
+        .unwrap_or(5.0)
+});
+
+pub static PREVIOUS_ROLLING_JACKPOT: Lazy<f64> = Lazy::new(|| {
+    std::env::var("PREVIOUS_ROLLING_JACKPOT")
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .unwrap_or(0.0)
+});
+
+pub struct Data {
+    pub bank: Arc<dyn LibcoinBank>,
+    pub slot_machines: MachineRegistry,
+    pub roulette_tables: ChannelSessions<RouletteRound>,
+    pub blackjack_shoes: ShoeRack,
+}
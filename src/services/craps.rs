@@ -0,0 +1,148 @@
+use crate::services::database::connection;
+use crate::services::settlement::{hold_wager, record_settlement, WagerId};
+use crate::Error;
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+pub struct CrapsBetRow {
+    pub id: i64,
+    pub kind: String,
+    pub point: Option<u8>,
+    pub amount: f64,
+    pub backs: Option<i64>,
+    pub wager_id: WagerId,
+}
+
+pub struct CrapsTableRow {
+    pub point: Option<u8>,
+    pub version: i64,
+    pub bets: Vec<CrapsBetRow>,
+}
+
+/// What a roll did to one bet on the table.
+pub enum CrapsBetChange {
+    /// A come or don't come bet moved to its own point.
+    Travelled { bet_id: i64, point: u8 },
+    /// The bet came off the table, paying `payout` (nothing for a loss).
+    Settled { bet_id: i64, wager_id: WagerId, payout: f64 },
+}
+
+/// The player's table, empty with no point for someone who's never played.
+pub fn craps_table(user_id: u64) -> Result<CrapsTableRow, Error> {
+    let connection = connection()?;
+    let (point, version) = connection
+        .query_row(
+            "SELECT point, version FROM craps_tables WHERE user_id = ?1",
+            params![user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .unwrap_or((None, 0));
+
+    let mut statement = connection.prepare(
+        "SELECT id, kind, point, amount, backs, wager_id FROM craps_bets WHERE user_id = ?1 ORDER BY id",
+    )?;
+    let bets = statement
+        .query_map(params![user_id], |row| {
+            Ok(CrapsBetRow {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                point: row.get(2)?,
+                amount: row.get(3)?,
+                backs: row.get(4)?,
+                wager_id: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(CrapsTableRow { point, version, bets })
+}
+
+/// Puts a staked bet on the player's table and holds its wager there until a roll settles it.
+/// Fails if the table has changed since `version` was read.
+pub fn add_craps_bet(
+    user_id: u64,
+    version: i64,
+    kind: &str,
+    point: Option<u8>,
+    amount: f64,
+    backs: Option<i64>,
+    wager_id: WagerId,
+) -> Result<(), Error> {
+    let mut connection = connection()?;
+    let transaction = connection.transaction()?;
+    let now = Utc::now().to_rfc3339();
+
+    bump_version(&transaction, user_id, version, None, &now, false)?;
+    transaction.execute(
+        "INSERT INTO craps_bets (user_id, kind, point, amount, backs, wager_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![user_id, kind, point, amount, backs, wager_id, now],
+    )?;
+    hold_wager(&transaction, wager_id)?;
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Records a roll: moves the puck to `point`, applies `changes` and settles every bet that came
+/// off, all at once. Returns the payouts to deliver. Fails without changing anything if the table
+/// has changed since `version` was read.
+pub fn record_craps_roll(
+    user_id: u64,
+    version: i64,
+    point: Option<u8>,
+    changes: &[CrapsBetChange],
+    message: &str,
+) -> Result<Vec<i64>, Error> {
+    let mut connection = connection()?;
+    let transaction = connection.transaction()?;
+    let now = Utc::now().to_rfc3339();
+
+    bump_version(&transaction, user_id, version, point, &now, true)?;
+    let mut payout_entries = Vec::new();
+    for change in changes {
+        match change {
+            CrapsBetChange::Travelled { bet_id, point } => {
+                transaction.execute(
+                    "UPDATE craps_bets SET point = ?1 WHERE id = ?2",
+                    params![point, bet_id],
+                )?;
+            }
+            CrapsBetChange::Settled { bet_id, wager_id, payout } => {
+                transaction.execute("DELETE FROM craps_bets WHERE id = ?1", params![bet_id])?;
+                payout_entries.extend(record_settlement(&transaction, *wager_id, user_id, *payout, message)?);
+            }
+        }
+    }
+    transaction.commit()?;
+
+    Ok(payout_entries)
+}
+
+/// Claims the table at `version` for a change, creating it the first time the player bets.
+fn bump_version(
+    transaction: &rusqlite::Transaction,
+    user_id: u64,
+    version: i64,
+    point: Option<u8>,
+    now: &str,
+    move_point: bool,
+) -> Result<(), Error> {
+    transaction.execute(
+        "INSERT INTO craps_tables (user_id, point, version, updated_at) VALUES (?1, NULL, 0, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, now],
+    )?;
+    let claimed = transaction.execute(
+        "UPDATE craps_tables SET version = version + 1, updated_at = ?1,
+             point = CASE WHEN ?2 THEN ?3 ELSE point END
+         WHERE user_id = ?4 AND version = ?5",
+        params![now, move_point, point, user_id, version],
+    )?;
+    if claimed == 0 {
+        return Err(Error::from("Your craps table changed while that was happening. Take another look and try again."));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use crate::Error;
+use once_cell::sync::Lazy;
+use rusqlite::Connection;
+use std::sync::{Mutex, MutexGuard};
+
+// Applied in order and tracked through `PRAGMA user_version`; only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS rolling_jackpots (
+        machine_name TEXT PRIMARY KEY,
+        value REAL NOT NULL,
+        updated_at TEXT NOT NULL
+    );",
+    "CREATE TABLE wagers (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        game TEXT NOT NULL,
+        stake REAL NOT NULL,
+        payout REAL,
+        status TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE INDEX wagers_status ON wagers (status);
+    CREATE TABLE payout_outbox (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        wager_id INTEGER NOT NULL REFERENCES wagers (id),
+        recipient_id INTEGER NOT NULL,
+        amount REAL NOT NULL,
+        message TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at INTEGER NOT NULL,
+        last_error TEXT,
+        delivered_at TEXT
+    );
+    CREATE INDEX payout_outbox_pending ON payout_outbox (delivered_at, next_attempt_at);",
+    "ALTER TABLE wagers ADD COLUMN house_id INTEGER;
+    ALTER TABLE payout_outbox ADD COLUMN sender_id INTEGER;",
+    "CREATE TABLE slot_spins (
+        wager_id INTEGER PRIMARY KEY REFERENCES wagers (id),
+        machine_name TEXT NOT NULL,
+        seed TEXT NOT NULL,
+        jackpot_before REAL NOT NULL,
+        payout INTEGER NOT NULL,
+        created_at TEXT NOT NULL
+    );",
+    "CREATE TABLE fairness_seeds (
+        user_id INTEGER PRIMARY KEY,
+        server_seed TEXT NOT NULL,
+        client_seed TEXT NOT NULL,
+        nonce INTEGER NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE revealed_server_seeds (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        server_seed TEXT NOT NULL,
+        server_seed_hash TEXT NOT NULL,
+        client_seed TEXT NOT NULL,
+        nonces_used INTEGER NOT NULL,
+        revealed_at TEXT NOT NULL
+    );
+    ALTER TABLE slot_spins ADD COLUMN server_seed_hash TEXT;
+    ALTER TABLE slot_spins ADD COLUMN client_seed TEXT;
+    ALTER TABLE slot_spins ADD COLUMN nonce INTEGER;",
+    "CREATE TABLE free_spins (
+        user_id INTEGER NOT NULL,
+        machine_name TEXT NOT NULL,
+        remaining INTEGER NOT NULL,
+        updated_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, machine_name)
+    );
+    ALTER TABLE slot_spins ADD COLUMN free_spin INTEGER NOT NULL DEFAULT 0;",
+    "CREATE TABLE jackpot_pools (
+        name TEXT PRIMARY KEY,
+        value REAL NOT NULL,
+        updated_at TEXT NOT NULL
+    );",
+    "CREATE TABLE jackpot_hits (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        wager_id INTEGER NOT NULL REFERENCES wagers (id),
+        user_id INTEGER NOT NULL,
+        guild_id INTEGER,
+        machine_name TEXT NOT NULL,
+        jackpot TEXT NOT NULL,
+        amount REAL NOT NULL,
+        hit_at TEXT NOT NULL
+    );
+    CREATE INDEX jackpot_hits_amount ON jackpot_hits (amount);
+    CREATE TABLE guild_settings (
+        guild_id INTEGER PRIMARY KEY,
+        jackpot_channel_id INTEGER,
+        updated_at TEXT NOT NULL
+    );",
+    "CREATE TABLE slot_spins_by_id (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        wager_id INTEGER NOT NULL REFERENCES wagers (id),
+        machine_name TEXT NOT NULL,
+        seed TEXT NOT NULL,
+        jackpot_before REAL NOT NULL,
+        payout INTEGER NOT NULL,
+        created_at TEXT NOT NULL,
+        server_seed_hash TEXT,
+        client_seed TEXT,
+        nonce INTEGER,
+        free_spin INTEGER NOT NULL DEFAULT 0,
+        bet INTEGER NOT NULL DEFAULT 1
+    );
+    INSERT INTO slot_spins_by_id (wager_id, machine_name, seed, jackpot_before, payout, created_at, server_seed_hash, client_seed, nonce, free_spin)
+        SELECT wager_id, machine_name, seed, jackpot_before, payout, created_at, server_seed_hash, client_seed, nonce, free_spin
+        FROM slot_spins ORDER BY wager_id;
+    DROP TABLE slot_spins;
+    ALTER TABLE slot_spins_by_id RENAME TO slot_spins;
+    CREATE INDEX slot_spins_wager ON slot_spins (wager_id);
+    CREATE INDEX slot_spins_server_seed ON slot_spins (server_seed_hash);",
+    "CREATE TABLE craps_tables (
+        user_id INTEGER PRIMARY KEY,
+        point INTEGER,
+        version INTEGER NOT NULL DEFAULT 0,
+        updated_at TEXT NOT NULL
+    );
+    CREATE TABLE craps_bets (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        point INTEGER,
+        amount REAL NOT NULL,
+        backs INTEGER REFERENCES craps_bets (id),
+        wager_id INTEGER NOT NULL REFERENCES wagers (id),
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX craps_bets_user ON craps_bets (user_id);",
+    "ALTER TABLE payout_outbox ADD COLUMN status TEXT NOT NULL DEFAULT 'pending';
+    UPDATE payout_outbox SET status = 'delivered' WHERE delivered_at IS NOT NULL;
+    DROP INDEX payout_outbox_pending;
+    CREATE INDEX payout_outbox_pending ON payout_outbox (status, next_attempt_at);",
+    "CREATE TABLE free_spin_wagers (
+        wager_id INTEGER PRIMARY KEY REFERENCES wagers (id),
+        user_id INTEGER NOT NULL,
+        machine_name TEXT NOT NULL,
+        spins INTEGER NOT NULL,
+        created_at TEXT NOT NULL
+    );",
+    "ALTER TABLE slot_spins ADD COLUMN definition_hash TEXT;",
+];
+
+pub static DATABASE_PATH: Lazy<String> = Lazy::new(|| {
+    std::env::var("DATABASE_PATH").unwrap_or_else(|_| "mr_house.db".to_string())
+});
+
+static DATABASE: Lazy<Mutex<Connection>> = Lazy::new(|| {
+    let mut connection = Connection::open(DATABASE_PATH.as_str())
+        .unwrap_or_else(|e| panic!("Failed to open database at {}: {}", *DATABASE_PATH, e));
+    migrate(&mut connection).expect("Failed to migrate database schema");
+    Mutex::new(connection)
+});
+
+fn migrate(connection: &mut Connection) -> Result<(), Error> {
+    let applied: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+pub fn connection() -> Result<MutexGuard<'static, Connection>, Error> {
+    DATABASE
+        .lock()
+        .map_err(|_| Error::from("Database connection lock was poisoned"))
+}
@@ -0,0 +1,166 @@
+use crate::services::database::connection;
+use crate::Error;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// What a player can see about their seeds before a reveal: the server seed only as its hash.
+pub struct FairnessCommitment {
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub next_nonce: u64,
+}
+
+/// The inputs behind one provably-fair spin.
+pub struct FairSpin {
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonce: u64,
+    pub seed: u64,
+}
+
+pub struct RevealedSeed {
+    pub server_seed: String,
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonces_used: u64,
+}
+
+/// Spin seed = the first 8 bytes (big-endian) of SHA-256("<server seed>:<client seed>:<nonce>"),
+/// with the server seed written as the hex string revealed by `/verify`.
+pub fn derive_spin_seed(server_seed: &str, client_seed: &str, nonce: u64) -> u64 {
+    let digest = Sha256::digest(format!("{}:{}:{}", server_seed, client_seed, nonce).as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digests are 32 bytes"))
+}
+
+pub fn hash_server_seed(server_seed: &str) -> String {
+    to_hex(&Sha256::digest(server_seed.as_bytes()))
+}
+
+pub fn get_commitment(user_id: u64) -> Result<FairnessCommitment, Error> {
+    let connection = connection()?;
+    let (server_seed, client_seed, next_nonce) = current_seeds(&connection, user_id)?;
+    Ok(FairnessCommitment {
+        server_seed_hash: hash_server_seed(&server_seed),
+        client_seed,
+        next_nonce,
+    })
+}
+
+/// Uses up the player's next `count` nonces and returns the seeds their spins must be played
+/// with, in order. The nonces are read and advanced in one immediate transaction, so no two
+/// batches can be handed the same ones.
+pub fn next_fair_spins(user_id: u64, count: u32) -> Result<Vec<FairSpin>, Error> {
+    let mut connection = connection()?;
+    let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let (server_seed, client_seed, first_nonce) = current_seeds(&transaction, user_id)?;
+    transaction.execute(
+        "UPDATE fairness_seeds SET nonce = nonce + ?2 WHERE user_id = ?1",
+        params![user_id, count],
+    )?;
+    transaction.commit()?;
+
+    let server_seed_hash = hash_server_seed(&server_seed);
+    Ok((first_nonce..first_nonce + count as u64)
+        .map(|nonce| FairSpin {
+            server_seed_hash: server_seed_hash.clone(),
+            seed: derive_spin_seed(&server_seed, &client_seed, nonce),
+            client_seed: client_seed.clone(),
+            nonce,
+        })
+        .collect())
+}
+
+pub fn set_client_seed(user_id: u64, client_seed: &str) -> Result<(), Error> {
+    let connection = connection()?;
+    current_seeds(&connection, user_id)?;
+    connection.execute(
+        "UPDATE fairness_seeds SET client_seed = ?1 WHERE user_id = ?2",
+        params![client_seed, user_id],
+    )?;
+    Ok(())
+}
+
+/// Reveals the player's current server seed and commits to a fresh one. Spins already played
+/// can then be checked against the hash that was published before they happened.
+pub fn rotate_server_seed(user_id: u64) -> Result<RevealedSeed, Error> {
+    let mut connection = connection()?;
+    let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let (server_seed, client_seed, nonces_used) = current_seeds(&transaction, user_id)?;
+    let server_seed_hash = hash_server_seed(&server_seed);
+
+    transaction.execute(
+        "INSERT INTO revealed_server_seeds (user_id, server_seed, server_seed_hash, client_seed, nonces_used, revealed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![user_id, server_seed, server_seed_hash, client_seed, nonces_used, Utc::now().to_rfc3339()],
+    )?;
+    transaction.execute(
+        "UPDATE fairness_seeds SET server_seed = ?1, nonce = 0, created_at = ?2 WHERE user_id = ?3",
+        params![generate_seed(32), Utc::now().to_rfc3339(), user_id],
+    )?;
+    transaction.commit()?;
+
+    Ok(RevealedSeed {
+        server_seed,
+        server_seed_hash,
+        client_seed,
+        nonces_used,
+    })
+}
+
+/// The player's seeds, creating a committed server seed and a random client seed the first time.
+fn current_seeds(connection: &Connection, user_id: u64) -> Result<(String, String, u64), Error> {
+    let existing = connection
+        .query_row(
+            "SELECT server_seed, client_seed, nonce FROM fairness_seeds WHERE user_id = ?1",
+            params![user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    if let Some(seeds) = existing {
+        return Ok(seeds);
+    }
+
+    let seeds = (generate_seed(32), generate_seed(8), 0);
+    connection.execute(
+        "INSERT INTO fairness_seeds (user_id, server_seed, client_seed, nonce, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![user_id, seeds.0, seeds.1, seeds.2, Utc::now().to_rfc3339()],
+    )?;
+    Ok(seeds)
+}
+
+fn generate_seed(byte_count: usize) -> String {
+    let bytes: Vec<u8> = (0..byte_count).map(|_| rand::random::<u8>()).collect();
+    to_hex(&bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spin_seeds_match_the_published_recipe() {
+        // The first 8 bytes of SHA-256("abc:def:0") and friends, worked out outside the bot.
+        assert_eq!(derive_spin_seed("abc", "def", 0), 0xc367_081c_0f38_4b4c);
+        assert_eq!(derive_spin_seed("abc", "def", 1), 0x0276_0b43_ba3d_2d7f);
+        assert_eq!(derive_spin_seed("0123456789abcdef", "player", 42), 0x2532_c3fb_8625_6efd);
+    }
+
+    #[test]
+    fn server_seeds_are_committed_as_their_sha256() {
+        assert_eq!(
+            hash_server_seed("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(to_hex(&[0x00, 0x0f, 0xff]), "000fff");
+    }
+}
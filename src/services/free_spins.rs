@@ -0,0 +1,112 @@
+use crate::services::database::connection;
+use crate::services::settlement::{open_unstaked_wager, WagerId};
+use crate::Error;
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+/// Free spins a player has left on a machine from bonus rounds they've triggered.
+pub fn free_spins_remaining(user_id: u64, machine_name: &str) -> Result<u32, Error> {
+    let connection = connection()?;
+    let remaining = connection
+        .query_row(
+            "SELECT remaining FROM free_spins WHERE user_id = ?1 AND machine_name = ?2",
+            params![user_id, machine_name],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(remaining.unwrap_or(0))
+}
+
+/// A machine the player still has free spins on, if any, preferring the one with the most left.
+pub fn machine_with_free_spins(user_id: u64) -> Result<Option<String>, Error> {
+    let connection = connection()?;
+    let machine_name = connection
+        .query_row(
+            "SELECT machine_name FROM free_spins WHERE user_id = ?1 AND remaining > 0
+             ORDER BY remaining DESC, machine_name LIMIT 1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(machine_name)
+}
+
+pub fn award_free_spins(user_id: u64, machine_name: &str, count: u32) -> Result<u32, Error> {
+    let connection = connection()?;
+    let remaining = connection.query_row(
+        "INSERT INTO free_spins (user_id, machine_name, remaining, updated_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(user_id, machine_name) DO UPDATE SET
+             remaining = remaining + excluded.remaining, updated_at = excluded.updated_at
+         RETURNING remaining",
+        params![user_id, machine_name, count, Utc::now().to_rfc3339()],
+        |row| row.get(0),
+    )?;
+
+    Ok(remaining)
+}
+
+/// Uses up to `count` of the player's free spins and opens the wager they're played under, together,
+/// so spins taken for a game that never settles can be handed back by `return_free_spins`. Returns the
+/// wager, how many spins were taken and how many are left afterwards, or `None` if they had none to use.
+pub fn take_free_spins(
+    user_id: u64,
+    machine_name: &str,
+    count: u32,
+    game: &str,
+    house_id: u64,
+) -> Result<Option<(WagerId, u32, u32)>, Error> {
+    let mut connection = connection()?;
+    let transaction = connection.transaction()?;
+    let remaining: Option<u32> = transaction
+        .query_row(
+            "SELECT remaining FROM free_spins WHERE user_id = ?1 AND machine_name = ?2 AND remaining > 0",
+            params![user_id, machine_name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(remaining) = remaining else {
+        return Ok(None);
+    };
+
+    // Holding the connection keeps anyone else from taking the same spins in between.
+    let taken = remaining.min(count);
+    let now = Utc::now().to_rfc3339();
+    transaction.execute(
+        "UPDATE free_spins SET remaining = remaining - ?3, updated_at = ?4 WHERE user_id = ?1 AND machine_name = ?2",
+        params![user_id, machine_name, taken, now],
+    )?;
+    let wager_id = open_unstaked_wager(&transaction, user_id, game, house_id)?;
+    transaction.execute(
+        "INSERT INTO free_spin_wagers (wager_id, user_id, machine_name, spins, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![wager_id, user_id, machine_name, taken, now],
+    )?;
+    transaction.commit()?;
+
+    Ok(Some((wager_id, taken, remaining - taken)))
+}
+
+/// Gives back the free spins an interrupted wager was played with, returning how many, if any.
+/// Run inside the transaction that refunds the wager.
+pub fn return_free_spins(connection: &rusqlite::Connection, wager_id: WagerId) -> Result<Option<u32>, Error> {
+    let taken: Option<(u64, String, u32)> = connection
+        .query_row(
+            "SELECT user_id, machine_name, spins FROM free_spin_wagers WHERE wager_id = ?1",
+            params![wager_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((user_id, machine_name, spins)) = taken else {
+        return Ok(None);
+    };
+
+    connection.execute(
+        "INSERT INTO free_spins (user_id, machine_name, remaining, updated_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(user_id, machine_name) DO UPDATE SET
+             remaining = remaining + excluded.remaining, updated_at = excluded.updated_at",
+        params![user_id, machine_name, spins, Utc::now().to_rfc3339()],
+    )?;
+
+    Ok(Some(spins))
+}
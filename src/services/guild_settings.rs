@@ -0,0 +1,31 @@
+use crate::services::database::connection;
+use crate::Error;
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+/// The channel a guild wants jackpot hits announced in, if it has picked one.
+pub fn jackpot_channel(guild_id: u64) -> Result<Option<u64>, Error> {
+    let connection = connection()?;
+    let channel_id = connection
+        .query_row(
+            "SELECT jackpot_channel_id FROM guild_settings WHERE guild_id = ?1",
+            params![guild_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(channel_id.flatten())
+}
+
+/// `None` stops the guild's jackpot announcements.
+pub fn set_jackpot_channel(guild_id: u64, channel_id: Option<u64>) -> Result<(), Error> {
+    let connection = connection()?;
+    connection.execute(
+        "INSERT INTO guild_settings (guild_id, jackpot_channel_id, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(guild_id) DO UPDATE SET
+             jackpot_channel_id = excluded.jackpot_channel_id, updated_at = excluded.updated_at",
+        params![guild_id, channel_id, Utc::now().to_rfc3339()],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::services::database::connection;
+use crate::services::settlement::WagerId;
+use crate::Error;
+use rusqlite::params;
+
+pub struct JackpotHit {
+    pub user_id: u64,
+    /// Where the jackpot was won, or `None` for a direct message.
+    pub guild_id: Option<u64>,
+    pub machine_name: String,
+    /// Which jackpot was hit, as shown to players: the machine's own or one of the shared pools.
+    pub jackpot: String,
+    pub amount: f64,
+    /// RFC 3339.
+    pub hit_at: String,
+}
+
+pub fn record_jackpot_hit(wager_id: WagerId, hit: &JackpotHit) -> Result<(), Error> {
+    let connection = connection()?;
+    connection.execute(
+        "INSERT INTO jackpot_hits (wager_id, user_id, guild_id, machine_name, jackpot, amount, hit_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![wager_id, hit.user_id, hit.guild_id, hit.machine_name, hit.jackpot, hit.amount, hit.hit_at],
+    )?;
+
+    Ok(())
+}
+
+/// The biggest jackpots ever won, largest first, limited to one guild when `guild_id` is given.
+pub fn biggest_jackpot_hits(guild_id: Option<u64>, limit: usize) -> Result<Vec<JackpotHit>, Error> {
+    let connection = connection()?;
+    let mut statement = connection.prepare(
+        "SELECT user_id, guild_id, machine_name, jackpot, amount, hit_at FROM jackpot_hits
+         WHERE ?1 IS NULL OR guild_id = ?1
+         ORDER BY amount DESC, id
+         LIMIT ?2",
+    )?;
+    let hits = statement
+        .query_map(params![guild_id, limit as i64], |row| {
+            Ok(JackpotHit {
+                user_id: row.get(0)?,
+                guild_id: row.get(1)?,
+                machine_name: row.get(2)?,
+                jackpot: row.get(3)?,
+                amount: row.get(4)?,
+                hit_at: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(hits)
+}
@@ -0,0 +1,99 @@
+use crate::services::database::connection;
+use crate::Error;
+use chrono::Utc;
+use rusqlite::params;
+use std::collections::HashMap;
+use tracing::error;
+
+/// Starts a pool off at its seed the first time it's seen. Pools already in play keep their value.
+pub fn ensure_jackpot_pool(name: &str, seed: u32) -> Result<(), Error> {
+    let connection = connection()?;
+    connection.execute(
+        "INSERT INTO jackpot_pools (name, value, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(name) DO NOTHING",
+        params![name, seed, Utc::now().to_rfc3339()],
+    )?;
+
+    Ok(())
+}
+
+pub fn jackpot_pool_values() -> Result<HashMap<String, f64>, Error> {
+    let connection = connection()?;
+    let mut statement = connection.prepare("SELECT name, value FROM jackpot_pools")?;
+    let values = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<HashMap<String, f64>, _>>()?;
+
+    Ok(values)
+}
+
+/// What one spin puts into the pools and which of them it won.
+pub struct PoolSettlement<'a> {
+    pub contributions: Vec<(&'a str, f64)>,
+    pub wins: Vec<(&'a str, u32)>,
+}
+
+impl PoolSettlement<'_> {
+    fn is_empty(&self) -> bool {
+        self.contributions.is_empty() && self.wins.is_empty()
+    }
+}
+
+/// Settles a batch of spins in order, in one transaction so two machines can't both win the same
+/// pot. Each spin adds its contributions to their pools, then pays out the whole Libcoin in each pool
+/// it won and starts it again from its seed plus the fraction left over. A spin that can't be settled
+/// is rolled back and the batch stops there, so the spins before it still stand. Returns what each
+/// settled spin's won pools paid, in the order its `wins` lists them.
+pub fn settle_jackpot_pools(spins: &[PoolSettlement]) -> Result<Vec<Vec<f64>>, Error> {
+    if spins.iter().all(PoolSettlement::is_empty) {
+        return Ok(spins.iter().map(|_| Vec::new()).collect());
+    }
+
+    let mut connection = connection()?;
+    let mut transaction = connection.transaction()?;
+    let now = Utc::now().to_rfc3339();
+
+    let mut settled = Vec::with_capacity(spins.len());
+    for (index, spin) in spins.iter().enumerate() {
+        let savepoint = transaction.savepoint()?;
+        match settle_spin(&savepoint, spin, &now) {
+            Ok(won) => {
+                savepoint.commit()?;
+                settled.push(won);
+            }
+            Err(reason) => {
+                error!("Couldn't settle the pools for spin {} of {}: {reason:?}", index + 1, spins.len());
+                break;
+            }
+        }
+    }
+    transaction.commit()?;
+
+    Ok(settled)
+}
+
+fn settle_spin(connection: &rusqlite::Connection, spin: &PoolSettlement, now: &str) -> Result<Vec<f64>, Error> {
+    for (name, amount) in &spin.contributions {
+        connection.execute(
+            "UPDATE jackpot_pools SET value = value + ?2, updated_at = ?3 WHERE name = ?1",
+            params![name, amount, now],
+        )?;
+    }
+
+    let mut won = Vec::with_capacity(spin.wins.len());
+    for (name, seed) in &spin.wins {
+        let value: f64 = connection.query_row(
+            "SELECT value FROM jackpot_pools WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )?;
+        let paid = value.floor();
+        connection.execute(
+            "UPDATE jackpot_pools SET value = ?2, updated_at = ?3 WHERE name = ?1",
+            params![name, *seed as f64 + (value - paid), now],
+        )?;
+        won.push(paid);
+    }
+
+    Ok(won)
+}
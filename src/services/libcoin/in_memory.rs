@@ -0,0 +1,121 @@
+use super::{LibcoinBank, LibcoinTransactionRecord};
+use crate::Error;
+use chrono::Utc;
+use poise::serenity_prelude::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Panopticon records grants and deductions against the bank itself, which has no Discord id.
+const BANK_USER: &str = "0";
+const GRANT_TRANSACTION_TYPE: i32 = 0;
+const DEDUCT_TRANSACTION_TYPE: i32 = 1;
+const TRANSFER_TRANSACTION_TYPE: i32 = 2;
+
+#[derive(Default)]
+struct Ledger {
+    balances: HashMap<u64, f64>,
+    transactions: Vec<LibcoinTransactionRecord>,
+}
+
+/// A process-local stand-in for Panopticon, for local development and tests.
+/// Every user starts with `starting_balance` the first time they are seen.
+pub struct InMemoryBank {
+    starting_balance: f64,
+    ledger: Mutex<Ledger>,
+}
+
+impl InMemoryBank {
+    pub fn new(starting_balance: f64) -> Self {
+        InMemoryBank {
+            starting_balance,
+            ledger: Mutex::new(Ledger::default()),
+        }
+    }
+
+    fn lock_ledger(&self) -> Result<std::sync::MutexGuard<'_, Ledger>, Error> {
+        self.ledger
+            .lock()
+            .map_err(|_| Error::from("In-memory ledger lock was poisoned"))
+    }
+
+    fn record(
+        ledger: &mut Ledger,
+        sending_user: String,
+        receiving_user: String,
+        amount: f64,
+        message: &str,
+        transaction_type: i32,
+    ) {
+        let id = ledger.transactions.len() as u64 + 1;
+        ledger.transactions.push(LibcoinTransactionRecord {
+            id,
+            sending_user,
+            receiving_user,
+            amount,
+            transaction_message: message.to_string(),
+            transaction_type,
+            transaction_date: Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+#[async_trait]
+impl LibcoinBank for InMemoryBank {
+    async fn get_libcoin_balance(&self, user_id: u64) -> Result<f64, Error> {
+        let ledger = self.lock_ledger()?;
+        Ok(*ledger.balances.get(&user_id).unwrap_or(&self.starting_balance))
+    }
+
+    async fn deduct_libcoin(&self, user_id: u64, amount: f64, message: &str) -> Result<(), Error> {
+        let mut ledger = self.lock_ledger()?;
+        let balance = *ledger.balances.get(&user_id).unwrap_or(&self.starting_balance);
+        if balance < amount {
+            return Err(Error::from(format!(
+                "Failed to deduct libcoin: user {} has {} but {} was requested",
+                user_id, balance, amount
+            )));
+        }
+
+        ledger.balances.insert(user_id, balance - amount);
+        Self::record(&mut ledger, user_id.to_string(), BANK_USER.to_string(), amount, message, DEDUCT_TRANSACTION_TYPE);
+        Ok(())
+    }
+
+    async fn grant_libcoin(&self, user_id: u64, amount: f64, message: &str) -> Result<(), Error> {
+        let mut ledger = self.lock_ledger()?;
+        let balance = *ledger.balances.get(&user_id).unwrap_or(&self.starting_balance);
+
+        ledger.balances.insert(user_id, balance + amount);
+        Self::record(&mut ledger, BANK_USER.to_string(), user_id.to_string(), amount, message, GRANT_TRANSACTION_TYPE);
+        Ok(())
+    }
+
+    async fn transfer_libcoin(&self, sender_id: u64, recipient_id: u64, amount: f64, message: &str) -> Result<(), Error> {
+        let mut ledger = self.lock_ledger()?;
+        let sender_balance = *ledger.balances.get(&sender_id).unwrap_or(&self.starting_balance);
+        if sender_balance < amount {
+            return Err(Error::from(format!(
+                "Failed to transfer libcoin: user {} has {} but {} was requested",
+                sender_id, sender_balance, amount
+            )));
+        }
+
+        ledger.balances.insert(sender_id, sender_balance - amount);
+        let recipient_balance = *ledger.balances.get(&recipient_id).unwrap_or(&self.starting_balance);
+        ledger.balances.insert(recipient_id, recipient_balance + amount);
+        Self::record(&mut ledger, sender_id.to_string(), recipient_id.to_string(), amount, message, TRANSFER_TRANSACTION_TYPE);
+        Ok(())
+    }
+
+    async fn get_user_transactions(&self, user_id: u64) -> Result<Vec<LibcoinTransactionRecord>, Error> {
+        let ledger = self.lock_ledger()?;
+        let user_id = user_id.to_string();
+
+        Ok(ledger
+            .transactions
+            .iter()
+            .filter(|t| t.sending_user == user_id || t.receiving_user == user_id)
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,41 @@
+pub mod in_memory;
+pub mod panopticon;
+
+pub use in_memory::InMemoryBank;
+pub use panopticon::PanopticonBank;
+
+use crate::Error;
+use poise::serenity_prelude::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+#[allow(dead_code)]
+pub struct LibcoinTransactionRecord {
+    #[serde(rename = "id")]
+    id: u64,
+    #[serde(rename = "sendingUser")]
+    sending_user: String,
+    #[serde(rename = "receivingUser")]
+    receiving_user: String,
+    #[serde(rename = "amount")]
+    pub amount: f64,
+    #[serde(rename = "transactionMessage")]
+    pub transaction_message: String,
+    #[serde(rename = "transactionType")]
+    transaction_type: i32,
+    #[serde(rename = "transactionDate")]
+    transaction_date: String,
+}
+
+#[async_trait]
+pub trait LibcoinBank: Send + Sync {
+    async fn get_libcoin_balance(&self, user_id: u64) -> Result<f64, Error>;
+
+    async fn deduct_libcoin(&self, user_id: u64, amount: f64, message: &str) -> Result<(), Error>;
+
+    async fn grant_libcoin(&self, user_id: u64, amount: f64, message: &str) -> Result<(), Error>;
+
+    async fn transfer_libcoin(&self, sender_id: u64, recipient_id: u64, amount: f64, message: &str) -> Result<(), Error>;
+
+    async fn get_user_transactions(&self, user_id: u64) -> Result<Vec<LibcoinTransactionRecord>, Error>;
+}
@@ -0,0 +1,157 @@
+use super::{LibcoinBank, LibcoinTransactionRecord};
+use crate::Error;
+use poise::serenity_prelude::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::HashSet;
+
+const API_KEY_HEADER: &str = "ApiKey";
+
+#[derive(Serialize)]
+struct LibcoinTransactionPayload {
+    #[serde(rename = "UserId")]
+    user_id: String,
+    #[serde(rename = "Amount")]
+    amount: f64,
+    #[serde(rename = "Message")]
+    message: String,
+}
+
+#[derive(Serialize)]
+struct LibcoinTransferPayload {
+    #[serde(rename = "SendingUserId")]
+    sending_user_id: String,
+    #[serde(rename = "ReceivingUserId")]
+    receiving_user_id: String,
+    #[serde(rename = "Amount")]
+    amount: f64,
+    #[serde(rename = "Message")]
+    message: String,
+}
+
+pub struct PanopticonBank {
+    http_client: Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl PanopticonBank {
+    pub fn new(base_url: &str, api_key: &str) -> Self {
+        PanopticonBank {
+            http_client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+        }
+    }
+
+    async fn libcoin_transaction(&self, user_id: u64, amount: f64, message: &str, url: &str) -> Result<(), Error> {
+        let payload = LibcoinTransactionPayload {
+            user_id: user_id.to_string(),
+            amount,
+            message: message.to_string(),
+        };
+
+        self.post(url, &payload).await
+    }
+
+    async fn post<T: Serialize + Sync>(&self, url: &str, payload: &T) -> Result<(), Error> {
+        let response = self
+            .http_client
+            .post(url)
+            .header(API_KEY_HEADER, self.api_key.as_str())
+            .json(payload)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let error_body = response.text().await.unwrap_or_else(|e| format!("Failed to read error body: {}", e));
+            Err(Error::from(format!("Libcoin transaction failed: {}", error_body)))
+        }
+    }
+}
+
+#[async_trait]
+impl LibcoinBank for PanopticonBank {
+    async fn get_libcoin_balance(&self, user_id: u64) -> Result<f64, Error> {
+        let url = format!("{}/libcoin/{}", self.base_url, user_id);
+        let response_text = self
+            .http_client
+            .get(&url)
+            .header(API_KEY_HEADER, self.api_key.as_str())
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let balance: f64 = response_text.trim().parse()?;
+
+        Ok(balance)
+    }
+
+    async fn deduct_libcoin(&self, user_id: u64, amount: f64, message: &str) -> Result<(), Error> {
+        let url = format!("{}/libcoin/deduct", self.base_url);
+
+        self.libcoin_transaction(user_id, amount, message, &url).await
+        .map_err(|e| Error::from(format!("Failed to deduct libcoin: {}", e)))
+    }
+
+    async fn grant_libcoin(&self, user_id: u64, amount: f64, message: &str) -> Result<(), Error> {
+        let url = format!("{}/libcoin/grant", self.base_url);
+
+        self.libcoin_transaction(user_id, amount, message, &url).await
+        .map_err(|e| Error::from(format!("Failed to grant libcoin: {}", e)))
+    }
+
+    async fn transfer_libcoin(&self, sender_id: u64, recipient_id: u64, amount: f64, message: &str) -> Result<(), Error> {
+        let url = format!("{}/libcoin/transfer", self.base_url);
+        let payload = LibcoinTransferPayload {
+            sending_user_id: sender_id.to_string(),
+            receiving_user_id: recipient_id.to_string(),
+            amount,
+            message: message.to_string(),
+        };
+
+        self.post(&url, &payload).await
+        .map_err(|e| Error::from(format!("Failed to transfer libcoin: {}", e)))
+    }
+
+    async fn get_user_transactions(&self, user_id: u64) -> Result<Vec<LibcoinTransactionRecord>, Error> {
+        const PAGE_SIZE: usize = 10000;
+        let mut page_number = 1;
+        let mut all_transactions: Vec<LibcoinTransactionRecord> = Vec::new();
+        let mut seen_transaction_ids: HashSet<u64> = HashSet::new();
+
+        loop {
+            let url = format!(
+                "{}/libcoin/transactions/{}?pageSize={}&pageNumber={}",
+                self.base_url, user_id, PAGE_SIZE, page_number
+            );
+
+            let response = self
+                .http_client
+                .get(&url)
+                .header(API_KEY_HEADER, self.api_key.as_str())
+                .send()
+                .await?
+                .error_for_status()?;
+
+            let current_page_transactions: Vec<LibcoinTransactionRecord> = response.json().await?;
+
+            if current_page_transactions.is_empty() {
+                break;
+            }
+
+            for transaction in current_page_transactions {
+                if seen_transaction_ids.insert(transaction.id) {
+                    all_transactions.push(transaction);
+                }
+            }
+            page_number += 1;
+        }
+
+        Ok(all_transactions)
+    }
+}
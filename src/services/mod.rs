@@ -1 +1,11 @@
-pub mod libcoin;
\ No newline at end of file
+pub mod craps;
+pub mod database;
+pub mod fairness;
+pub mod free_spins;
+pub mod guild_settings;
+pub mod hall_of_fame;
+pub mod jackpot_pools;
+pub mod libcoin;
+pub mod rolling_jackpot;
+pub mod settlement;
+pub mod spin_history;
\ No newline at end of file
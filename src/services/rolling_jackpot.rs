@@ -0,0 +1,30 @@
+use crate::services::database::connection;
+use crate::Error;
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+pub fn load_rolling_jackpot(machine_name: &str) -> Result<Option<f64>, Error> {
+    let connection = connection()?;
+    let value = connection
+        .query_row(
+            "SELECT value FROM rolling_jackpots WHERE machine_name = ?1",
+            params![machine_name],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(value)
+}
+
+/// Overwrites the stored jackpot, so call it while still holding the machine's lock; saved after
+/// the lock is released, a slower spin can write its older value over a newer one.
+pub fn save_rolling_jackpot(machine_name: &str, value: f64) -> Result<(), Error> {
+    let connection = connection()?;
+    connection.execute(
+        "INSERT INTO rolling_jackpots (machine_name, value, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(machine_name) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        params![machine_name, value, Utc::now().to_rfc3339()],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,487 @@
+use crate::services::database::connection;
+use crate::services::free_spins::return_free_spins;
+use crate::services::libcoin::LibcoinBank;
+use crate::{Error, HOUSE_BANKROLL_FLOOR};
+use chrono::Utc;
+use rusqlite::params;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, warn};
+
+// A wager moves opened -> staked -> settled. Anything still opened or staked on startup was interrupted.
+// Bets left riding between commands go staked -> held -> settled instead, and survive a restart.
+const WAGER_OPENED: &str = "opened";
+const WAGER_STAKED: &str = "staked";
+const WAGER_HELD: &str = "held";
+const WAGER_SETTLED: &str = "settled";
+const WAGER_CANCELLED: &str = "cancelled";
+const WAGER_REFUNDED: &str = "refunded";
+const WAGER_ABANDONED: &str = "abandoned";
+
+// A payout moves pending -> sending -> delivered. One still sending was handed to the bank but never
+// confirmed, so it's set aside as unconfirmed for someone to check instead of being paid again.
+const PAYOUT_PENDING: &str = "pending";
+const PAYOUT_SENDING: &str = "sending";
+const PAYOUT_DELIVERED: &str = "delivered";
+const PAYOUT_UNCONFIRMED: &str = "unconfirmed";
+const PAYOUT_DEAD: &str = "dead";
+
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(15);
+const OUTBOX_BASE_BACKOFF_SECONDS: i64 = 10;
+const OUTBOX_MAX_BACKOFF_SECONDS: i64 = 3600;
+// With the backoff above that's most of a day; after that the payout waits in the dead letters.
+const OUTBOX_MAX_ATTEMPTS: u32 = 24;
+
+const REFUND_MESSAGE: &str = "Refund for an unfinished game";
+const BANK_TROUBLE: &str = "Sorry, looks like I'm having trouble contacting the bank.";
+const BOOKS_TROUBLE: &str =
+    "Well this is embarassing. I wanted to give you your winnings but it looks like I'm having trouble keeping my books.";
+
+pub type WagerId = i64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayoutStatus {
+    Delivered,
+    Queued,
+}
+
+struct OutboxEntry {
+    id: i64,
+    // `None` for entries queued before payouts came out of the house account; those are minted with a grant.
+    sender_id: Option<u64>,
+    recipient_id: u64,
+    amount: f64,
+    message: String,
+    attempts: u32,
+}
+
+/// Takes a game's `stake` from the player once they can afford it and the house can cover paying
+/// out `max_payout`, which for a bet left on a table means everything on it landing at once, not
+/// just the newest bet. A game the house can't lose on, like a duel, has no `max_payout` to check.
+/// This talks to the bank, which can take longer than Discord waits for an answer, so acknowledge
+/// any button press or menu pick that led here first.
+pub async fn stake_wager(
+    bank: &dyn LibcoinBank,
+    user_id: u64,
+    game: &str,
+    stake: f64,
+    max_payout: f64,
+    message: &str,
+    house_id: u64,
+) -> Result<WagerId, Error> {
+    let balance = bank.get_libcoin_balance(user_id).await.map_err(|_| Error::from(BANK_TROUBLE))?;
+    if balance < stake {
+        return Err(Error::from("You don't have enough libcoin to cover that bet!"));
+    }
+    if max_payout > 0.0 {
+        ensure_house_can_cover(bank, house_id, max_payout).await?;
+    }
+
+    place_wager(bank, user_id, game, stake, message, house_id)
+        .await
+        .map_err(|_| Error::from(BANK_TROUBLE))
+}
+
+/// `settle_wager`, with anything that goes wrong logged and put to the player as an apology.
+pub async fn settle(
+    bank: &dyn LibcoinBank,
+    wager_id: WagerId,
+    user_id: u64,
+    payout: f64,
+    message: &str,
+) -> Result<PayoutStatus, Error> {
+    settle_wager(bank, wager_id, user_id, payout, message).await.map_err(|reason| {
+        error!("Couldn't settle wager {wager_id}: {reason:?}");
+        Error::from(BOOKS_TROUBLE)
+    })
+}
+
+/// Gives the stake back on a game that never got going, most often because its message couldn't be
+/// sent: nobody can play a hand they can't see. If even that fails the wager stays staked, and
+/// `reconcile` refunds it on the next start.
+pub async fn call_off(bank: &dyn LibcoinBank, wager_id: WagerId) {
+    if let Err(reason) = refund_wager(bank, wager_id).await {
+        error!("Couldn't refund wager {wager_id}: {reason:?}");
+    }
+}
+
+/// A payout as the player sees it, noting when the bank has it queued.
+pub fn describe_payout(payout: impl std::fmt::Display, status: PayoutStatus) -> String {
+    match status {
+        PayoutStatus::Delivered => payout.to_string(),
+        PayoutStatus::Queued => format!("{} (the bank is slow to answer, your winnings will arrive shortly)", payout),
+    }
+}
+
+/// Refuses a game when paying out `max_payout` would take the house below `HOUSE_BANKROLL_FLOOR`.
+pub async fn ensure_house_can_cover(bank: &dyn LibcoinBank, house_id: u64, max_payout: f64) -> Result<(), Error> {
+    let house_balance = bank.get_libcoin_balance(house_id).await?;
+    if house_balance - max_payout < *HOUSE_BANKROLL_FLOOR {
+        return Err(Error::from(
+            "The house can't cover a win that big right now. Come back once the tables have had a chance to recover.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Moves `stake` from the player to `house_id`, recording the wager before any money moves so an
+/// interrupted game can be found again by `reconcile`. A zero stake, as on a free spin, moves nothing.
+pub async fn place_wager(
+    bank: &dyn LibcoinBank,
+    user_id: u64,
+    game: &str,
+    stake: f64,
+    message: &str,
+    house_id: u64,
+) -> Result<WagerId, Error> {
+    let wager_id = {
+        let connection = connection()?;
+        let now = Utc::now().to_rfc3339();
+        connection.execute(
+            "INSERT INTO wagers (user_id, house_id, game, stake, status, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            params![user_id, house_id, game, stake, WAGER_OPENED, now],
+        )?;
+        connection.last_insert_rowid()
+    };
+
+    if stake > 0.0 {
+        if let Err(reason) = bank.transfer_libcoin(user_id, house_id, stake, message).await {
+            update_wager_status(wager_id, WAGER_CANCELLED)?;
+            return Err(reason);
+        }
+    }
+    update_wager_status(wager_id, WAGER_STAKED)?;
+
+    Ok(wager_id)
+}
+
+/// Records the outcome of a staked wager and pays `payout` from the house to the player. If the bank
+/// can't be reached the payout stays in the outbox and is retried in the background until it goes through.
+pub async fn settle_wager(
+    bank: &dyn LibcoinBank,
+    wager_id: WagerId,
+    user_id: u64,
+    payout: f64,
+    message: &str,
+) -> Result<PayoutStatus, Error> {
+    let payout_entry = {
+        let mut connection = connection()?;
+        let transaction = connection.transaction()?;
+        let payout_entry = record_settlement(&transaction, wager_id, user_id, payout, message)?;
+        transaction.commit()?;
+        payout_entry
+    };
+
+    Ok(deliver_payouts(bank, payout_entry.as_slice()).await)
+}
+
+/// `settle_wager`'s bookkeeping on its own, done inside a game's transaction so the wager is
+/// settled in step with the game's own records. Hand back what it returns to `deliver_payouts`
+/// once the transaction has committed.
+pub fn record_settlement(
+    transaction: &rusqlite::Transaction,
+    wager_id: WagerId,
+    user_id: u64,
+    payout: f64,
+    message: &str,
+) -> Result<Option<i64>, Error> {
+    transaction.execute(
+        "UPDATE wagers SET payout = ?1, status = ?2, updated_at = ?3 WHERE id = ?4",
+        params![payout, WAGER_SETTLED, Utc::now().to_rfc3339(), wager_id],
+    )?;
+    let house_id: Option<u64> = transaction.query_row(
+        "SELECT house_id FROM wagers WHERE id = ?1",
+        params![wager_id],
+        |row| row.get(0),
+    )?;
+    if payout > 0.0 {
+        Ok(Some(insert_outbox_entry(transaction, wager_id, house_id, user_id, payout, message)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Tries recorded payouts straight away, leaving any the bank won't take yet to the outbox worker.
+pub async fn deliver_payouts(bank: &dyn LibcoinBank, entry_ids: &[i64]) -> PayoutStatus {
+    let mut status = PayoutStatus::Delivered;
+    for entry_id in entry_ids {
+        if !deliver_now(bank, *entry_id).await {
+            status = PayoutStatus::Queued;
+        }
+    }
+
+    status
+}
+
+/// Opens a wager with nothing to stake, as free spins are played under, inside the caller's
+/// transaction. It starts out staked, so `reconcile` treats it like any other unfinished game.
+pub fn open_unstaked_wager(
+    connection: &rusqlite::Connection,
+    user_id: u64,
+    game: &str,
+    house_id: u64,
+) -> Result<WagerId, Error> {
+    let now = Utc::now().to_rfc3339();
+    connection.execute(
+        "INSERT INTO wagers (user_id, house_id, game, stake, status, created_at, updated_at)
+         VALUES (?1, ?2, ?3, 0, ?4, ?5, ?5)",
+        params![user_id, house_id, game, WAGER_STAKED, now],
+    )?;
+    Ok(connection.last_insert_rowid())
+}
+
+/// Leaves a staked wager riding between commands, as a bet on a craps table does, so `reconcile`
+/// doesn't refund it on startup. Run it in the same transaction that records the bet.
+pub fn hold_wager(connection: &rusqlite::Connection, wager_id: WagerId) -> Result<(), Error> {
+    connection.execute(
+        "UPDATE wagers SET status = ?1, updated_at = ?2 WHERE id = ?3 AND status = ?4",
+        params![WAGER_HELD, Utc::now().to_rfc3339(), wager_id, WAGER_STAKED],
+    )?;
+    Ok(())
+}
+
+/// Refunds wagers whose stake was collected but never settled, sets aside payouts that were being
+/// sent when the bot stopped, then flushes anything left in the outbox. Run once on startup, before
+/// the bot accepts new games.
+pub async fn reconcile(bank: &dyn LibcoinBank) -> Result<(), Error> {
+    set_aside_unconfirmed_payouts()?;
+
+    let interrupted: Vec<(WagerId, u64, Option<u64>, f64, String)> = {
+        let connection = connection()?;
+        let mut statement = connection.prepare(
+            "SELECT id, user_id, house_id, stake, status FROM wagers WHERE status IN (?1, ?2)",
+        )?;
+        let rows = statement.query_map(params![WAGER_OPENED, WAGER_STAKED], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    for (wager_id, user_id, house_id, stake, status) in interrupted {
+        if status == WAGER_STAKED {
+            let mut connection = connection()?;
+            let transaction = connection.transaction()?;
+            record_refund(&transaction, wager_id, user_id, house_id, stake)?;
+            transaction.commit()?;
+        } else {
+            // We can't tell whether the deduction went through before the restart.
+            warn!("Wager {wager_id} for user {user_id} ({stake} libcoin) was interrupted before its stake was confirmed; needs manual review");
+            update_wager_status(wager_id, WAGER_ABANDONED)?;
+        }
+    }
+
+    deliver_due(bank).await
+}
+
+/// Calls off a staked wager there and then, the way `reconcile` would on startup, for a game that
+/// can't be finished.
+pub async fn refund_wager(bank: &dyn LibcoinBank, wager_id: WagerId) -> Result<PayoutStatus, Error> {
+    let refund_entry = {
+        let mut connection = connection()?;
+        let transaction = connection.transaction()?;
+        let (user_id, house_id, stake): (u64, Option<u64>, f64) = transaction.query_row(
+            "SELECT user_id, house_id, stake FROM wagers WHERE id = ?1 AND status = ?2",
+            params![wager_id, WAGER_STAKED],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let refund_entry = record_refund(&transaction, wager_id, user_id, house_id, stake)?;
+        transaction.commit()?;
+        refund_entry
+    };
+
+    Ok(deliver_payouts(bank, refund_entry.as_slice()).await)
+}
+
+/// Queues the stake of a staked wager back to the player and marks it refunded.
+fn record_refund(
+    transaction: &rusqlite::Transaction,
+    wager_id: WagerId,
+    user_id: u64,
+    house_id: Option<u64>,
+    stake: f64,
+) -> Result<Option<i64>, Error> {
+    let refund_entry = if stake > 0.0 {
+        Some(insert_outbox_entry(transaction, wager_id, house_id, user_id, stake, REFUND_MESSAGE)?)
+    } else {
+        None
+    };
+    // A free spin's wager has no stake to refund; the spins it used go back instead.
+    return_free_spins(transaction, wager_id)?;
+    transaction.execute(
+        "UPDATE wagers SET status = ?1, updated_at = ?2 WHERE id = ?3",
+        params![WAGER_REFUNDED, Utc::now().to_rfc3339(), wager_id],
+    )?;
+
+    Ok(refund_entry)
+}
+
+pub fn spawn_outbox_worker(bank: Arc<dyn LibcoinBank>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(OUTBOX_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(reason) = deliver_due(bank.as_ref()).await {
+                error!("Couldn't process payout outbox: {reason:?}");
+            }
+        }
+    });
+}
+
+async fn deliver_due(bank: &dyn LibcoinBank) -> Result<(), Error> {
+    let due: Vec<i64> = {
+        let connection = connection()?;
+        let mut statement = connection.prepare(
+            "SELECT id FROM payout_outbox WHERE status = ?1 AND next_attempt_at <= ?2 ORDER BY id",
+        )?;
+        let rows = statement.query_map(params![PAYOUT_PENDING, Utc::now().timestamp()], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    for entry_id in due {
+        deliver_now(bank, entry_id).await;
+    }
+
+    Ok(())
+}
+
+/// Attempts a single outbox entry, returning whether it has been delivered.
+async fn deliver_now(bank: &dyn LibcoinBank, entry_id: i64) -> bool {
+    let entry = match claim(entry_id) {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return false,
+        Err(reason) => {
+            error!("Couldn't claim payout {entry_id}: {reason:?}");
+            return false;
+        }
+    };
+
+    let result = match entry.sender_id {
+        Some(sender_id) => {
+            bank.transfer_libcoin(sender_id, entry.recipient_id, entry.amount, &entry.message)
+                .await
+        }
+        None => {
+            bank.grant_libcoin(entry.recipient_id, entry.amount, &entry.message)
+                .await
+        }
+    };
+
+    match &result {
+        Ok(()) => {
+            // Left as sending, the payout is set aside on the next startup rather than sent again.
+            if let Err(reason) = mark_delivered(entry.id) {
+                error!("Payout {} went through but couldn't be marked delivered: {reason:?}", entry.id);
+            }
+        }
+        Err(reason) => {
+            warn!("Payout {} of {} libcoin to {} failed (attempt {}): {reason}", entry.id, entry.amount, entry.recipient_id, entry.attempts + 1);
+            if let Err(reason) = mark_failed(&entry, &reason.to_string()) {
+                error!("Couldn't record outcome of payout {}: {reason:?}", entry.id);
+            }
+        }
+    }
+
+    result.is_ok()
+}
+
+fn claim(entry_id: i64) -> Result<Option<OutboxEntry>, Error> {
+    let connection = connection()?;
+    let now = Utc::now().timestamp();
+    let claimed = connection.execute(
+        "UPDATE payout_outbox SET status = ?1
+         WHERE id = ?2 AND status = ?3 AND next_attempt_at <= ?4",
+        params![PAYOUT_SENDING, entry_id, PAYOUT_PENDING, now],
+    )?;
+    if claimed == 0 {
+        return Ok(None);
+    }
+
+    let entry = connection.query_row(
+        "SELECT id, sender_id, recipient_id, amount, message, attempts FROM payout_outbox WHERE id = ?1",
+        params![entry_id],
+        |row| {
+            Ok(OutboxEntry {
+                id: row.get(0)?,
+                sender_id: row.get(1)?,
+                recipient_id: row.get(2)?,
+                amount: row.get(3)?,
+                message: row.get(4)?,
+                attempts: row.get(5)?,
+            })
+        },
+    )?;
+
+    Ok(Some(entry))
+}
+
+fn mark_delivered(entry_id: i64) -> Result<(), Error> {
+    let connection = connection()?;
+    connection.execute(
+        "UPDATE payout_outbox SET status = ?1, delivered_at = ?2, attempts = attempts + 1 WHERE id = ?3",
+        params![PAYOUT_DELIVERED, Utc::now().to_rfc3339(), entry_id],
+    )?;
+    Ok(())
+}
+
+fn mark_failed(entry: &OutboxEntry, reason: &str) -> Result<(), Error> {
+    let backoff = OUTBOX_BASE_BACKOFF_SECONDS
+        .saturating_mul(1 << entry.attempts.min(16))
+        .min(OUTBOX_MAX_BACKOFF_SECONDS);
+    let status = if entry.attempts + 1 >= OUTBOX_MAX_ATTEMPTS {
+        error!("Giving up on payout {} of {} libcoin to {} after {} attempts", entry.id, entry.amount, entry.recipient_id, OUTBOX_MAX_ATTEMPTS);
+        PAYOUT_DEAD
+    } else {
+        PAYOUT_PENDING
+    };
+    let connection = connection()?;
+    connection.execute(
+        "UPDATE payout_outbox SET status = ?1, attempts = attempts + 1, next_attempt_at = ?2, last_error = ?3 WHERE id = ?4",
+        params![status, Utc::now().timestamp() + backoff, reason, entry.id],
+    )?;
+    Ok(())
+}
+
+/// Payouts still marked sending on startup may or may not have reached the bank, so they're left for
+/// someone to check against the bank's records instead of being retried.
+fn set_aside_unconfirmed_payouts() -> Result<(), Error> {
+    let connection = connection()?;
+    let mut statement = connection.prepare("SELECT id, recipient_id, amount FROM payout_outbox WHERE status = ?1")?;
+    let unconfirmed: Vec<(i64, u64, f64)> = statement
+        .query_map(params![PAYOUT_SENDING], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+    for (entry_id, recipient_id, amount) in unconfirmed {
+        warn!("Payout {entry_id} of {amount} libcoin to {recipient_id} was being sent when the bot stopped; needs manual review");
+    }
+    connection.execute(
+        "UPDATE payout_outbox SET status = ?1 WHERE status = ?2",
+        params![PAYOUT_UNCONFIRMED, PAYOUT_SENDING],
+    )?;
+    Ok(())
+}
+
+fn insert_outbox_entry(
+    connection: &rusqlite::Connection,
+    wager_id: WagerId,
+    sender_id: Option<u64>,
+    recipient_id: u64,
+    amount: f64,
+    message: &str,
+) -> Result<i64, Error> {
+    connection.execute(
+        "INSERT INTO payout_outbox (wager_id, sender_id, recipient_id, amount, message, next_attempt_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![wager_id, sender_id, recipient_id, amount, message, Utc::now().timestamp()],
+    )?;
+    Ok(connection.last_insert_rowid())
+}
+
+fn update_wager_status(wager_id: WagerId, status: &str) -> Result<(), Error> {
+    let connection = connection()?;
+    connection.execute(
+        "UPDATE wagers SET status = ?1, updated_at = ?2 WHERE id = ?3",
+        params![status, Utc::now().to_rfc3339(), wager_id],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use crate::services::database::connection;
+use crate::services::fairness::FairSpin;
+use crate::services::settlement::WagerId;
+use crate::Error;
+use chrono::Utc;
+use rusqlite::params;
+
+/// A spin as it was recorded, with enough to replay it and check the seed it was played with.
+pub struct RecordedSpin {
+    pub wager_id: WagerId,
+    pub machine_name: String,
+    pub seed: String,
+    pub jackpot_before: f64,
+    pub payout: u64,
+    pub client_seed: String,
+    pub nonce: u64,
+    pub free_spin: bool,
+    /// Multiple of the machine's cost the spin was played at; `payout` is already scaled by it.
+    pub bet: u32,
+    /// The machine's definition hash when the spin was played, if it was recorded.
+    pub definition_hash: Option<String>,
+}
+
+/// Keeps what's needed to replay a spin later with `SlotMachine::replay`: the seed, the
+/// fairness inputs it was derived from, the rolling jackpot it was played against and the
+/// definition hash of the machine that played it. Several spins can share a wager when they were
+/// played in one go.
+#[allow(clippy::too_many_arguments)]
+pub fn record_spin(
+    wager_id: WagerId,
+    machine_name: &str,
+    definition_hash: &str,
+    fair_spin: &FairSpin,
+    jackpot_before: f64,
+    payout: u64,
+    free_spin: bool,
+    bet: u32,
+) -> Result<(), Error> {
+    let connection = connection()?;
+    connection.execute(
+        "INSERT INTO slot_spins (wager_id, machine_name, seed, jackpot_before, payout, created_at, server_seed_hash, client_seed, nonce, free_spin, bet, definition_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            wager_id,
+            machine_name,
+            format_seed(fair_spin.seed),
+            jackpot_before,
+            payout,
+            Utc::now().to_rfc3339(),
+            fair_spin.server_seed_hash,
+            fair_spin.client_seed,
+            fair_spin.nonce,
+            free_spin,
+            bet,
+            definition_hash
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// The most recent spins played under a server seed, newest first.
+pub fn spins_for_server_seed(server_seed_hash: &str, limit: usize) -> Result<Vec<RecordedSpin>, Error> {
+    let connection = connection()?;
+    let mut statement = connection.prepare(
+        "SELECT wager_id, machine_name, seed, jackpot_before, payout, client_seed, nonce, free_spin, bet, definition_hash FROM slot_spins
+         WHERE server_seed_hash = ?1 ORDER BY nonce DESC LIMIT ?2",
+    )?;
+    let spins = statement
+        .query_map(params![server_seed_hash, limit as i64], |row| {
+            Ok(RecordedSpin {
+                wager_id: row.get(0)?,
+                machine_name: row.get(1)?,
+                seed: row.get(2)?,
+                jackpot_before: row.get(3)?,
+                payout: row.get(4)?,
+                client_seed: row.get(5)?,
+                nonce: row.get(6)?,
+                free_spin: row.get(7)?,
+                bet: row.get(8)?,
+                definition_hash: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(spins)
+}
+
+// Seeds use the full u64 range, which SQLite integers can't hold, so they're stored as hex.
+pub fn format_seed(seed: u64) -> String {
+    format!("{:016x}", seed)
+}
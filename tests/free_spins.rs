@@ -0,0 +1,84 @@
+use mr_house::services::free_spins::{award_free_spins, free_spins_remaining, machine_with_free_spins, take_free_spins};
+use mr_house::services::libcoin::in_memory::InMemoryBank;
+use mr_house::services::libcoin::LibcoinBank;
+use mr_house::services::settlement::{refund_wager, settle_wager, PayoutStatus};
+use once_cell::sync::Lazy;
+use tokio::sync::{Mutex, MutexGuard};
+
+const HOUSE_ID: u64 = 1;
+const STARTING_BALANCE: f64 = 100.0;
+
+// Every test shares the one database, so they take turns.
+static DATABASE: Lazy<Mutex<()>> = Lazy::new(|| {
+    let path = std::env::temp_dir().join(format!("mr_house-free-spins-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    std::env::set_var("DATABASE_PATH", path);
+    Mutex::new(())
+});
+
+async fn scratch_database() -> MutexGuard<'static, ()> {
+    DATABASE.lock().await
+}
+
+async fn balance(bank: &InMemoryBank, user_id: u64) -> f64 {
+    bank.get_libcoin_balance(user_id).await.unwrap()
+}
+
+#[tokio::test]
+async fn awards_add_up_on_each_machine() {
+    let _database = scratch_database().await;
+
+    assert_eq!(free_spins_remaining(10, "gore").unwrap(), 0);
+    assert_eq!(machine_with_free_spins(10).unwrap(), None);
+
+    assert_eq!(award_free_spins(10, "gore", 5).unwrap(), 5);
+    assert_eq!(award_free_spins(10, "gore", 3).unwrap(), 8);
+    assert_eq!(award_free_spins(10, "fruit", 10).unwrap(), 10);
+    assert_eq!(free_spins_remaining(10, "gore").unwrap(), 8);
+    assert_eq!(free_spins_remaining(11, "gore").unwrap(), 0);
+    // The machine with the most left is played first.
+    assert_eq!(machine_with_free_spins(10).unwrap().as_deref(), Some("fruit"));
+}
+
+#[tokio::test]
+async fn taking_spins_uses_up_what_is_left() {
+    let _database = scratch_database().await;
+
+    assert!(take_free_spins(20, "gore", 3, "slots:gore", HOUSE_ID).unwrap().is_none());
+
+    award_free_spins(20, "gore", 5).unwrap();
+    let (_, taken, remaining) = take_free_spins(20, "gore", 3, "slots:gore", HOUSE_ID).unwrap().unwrap();
+    assert_eq!((taken, remaining), (3, 2));
+    // Asking for more than are left takes the rest.
+    let (_, taken, remaining) = take_free_spins(20, "gore", 3, "slots:gore", HOUSE_ID).unwrap().unwrap();
+    assert_eq!((taken, remaining), (2, 0));
+    assert!(take_free_spins(20, "gore", 1, "slots:gore", HOUSE_ID).unwrap().is_none());
+    assert_eq!(machine_with_free_spins(20).unwrap(), None);
+}
+
+#[tokio::test]
+async fn a_refunded_wager_hands_its_spins_back() {
+    let _database = scratch_database().await;
+    let bank = InMemoryBank::new(STARTING_BALANCE);
+
+    award_free_spins(30, "gore", 4).unwrap();
+    let (wager_id, taken, _) = take_free_spins(30, "gore", 4, "slots:gore", HOUSE_ID).unwrap().unwrap();
+    assert_eq!(taken, 4);
+    assert_eq!(free_spins_remaining(30, "gore").unwrap(), 0);
+
+    refund_wager(&bank, wager_id).await.unwrap();
+    assert_eq!(free_spins_remaining(30, "gore").unwrap(), 4);
+}
+
+#[tokio::test]
+async fn a_settled_wager_keeps_its_spins_played() {
+    let _database = scratch_database().await;
+    let bank = InMemoryBank::new(STARTING_BALANCE);
+
+    award_free_spins(40, "gore", 2).unwrap();
+    let (wager_id, _, _) = take_free_spins(40, "gore", 2, "slots:gore", HOUSE_ID).unwrap().unwrap();
+    let status = settle_wager(&bank, wager_id, 40, 15.0, "Winning").await.unwrap();
+    assert_eq!(status, PayoutStatus::Delivered);
+    assert_eq!(free_spins_remaining(40, "gore").unwrap(), 0);
+    assert_eq!(balance(&bank, 40).await, STARTING_BALANCE + 15.0);
+}
@@ -0,0 +1,91 @@
+use mr_house::services::libcoin::in_memory::InMemoryBank;
+use mr_house::services::libcoin::LibcoinBank;
+use mr_house::services::settlement::{place_wager, reconcile, settle_wager, PayoutStatus};
+use once_cell::sync::Lazy;
+use tokio::sync::{Mutex, MutexGuard};
+
+const HOUSE_ID: u64 = 1;
+const STARTING_BALANCE: f64 = 100.0;
+
+// Every test shares the one database, and `reconcile` sweeps up any wager left open in it, so they
+// take turns.
+static DATABASE: Lazy<Mutex<()>> = Lazy::new(|| {
+    let path = std::env::temp_dir().join(format!("mr_house-settlement-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    std::env::set_var("DATABASE_PATH", path);
+    Mutex::new(())
+});
+
+async fn scratch_database() -> MutexGuard<'static, ()> {
+    DATABASE.lock().await
+}
+
+async fn balance(bank: &InMemoryBank, user_id: u64) -> f64 {
+    bank.get_libcoin_balance(user_id).await.unwrap()
+}
+
+#[tokio::test]
+async fn winnings_come_out_of_the_house() {
+    let _database = scratch_database().await;
+    let bank = InMemoryBank::new(STARTING_BALANCE);
+
+    let wager_id = place_wager(&bank, 10, "test", 10.0, "Playing", HOUSE_ID).await.unwrap();
+    assert_eq!(balance(&bank, 10).await, 90.0);
+    assert_eq!(balance(&bank, HOUSE_ID).await, 110.0);
+
+    let status = settle_wager(&bank, wager_id, 10, 25.0, "Winning").await.unwrap();
+    assert_eq!(status, PayoutStatus::Delivered);
+    assert_eq!(balance(&bank, 10).await, 115.0);
+    assert_eq!(balance(&bank, HOUSE_ID).await, 85.0);
+}
+
+#[tokio::test]
+async fn losses_stay_with_the_house() {
+    let _database = scratch_database().await;
+    let bank = InMemoryBank::new(STARTING_BALANCE);
+
+    let wager_id = place_wager(&bank, 20, "test", 10.0, "Playing", HOUSE_ID).await.unwrap();
+    let status = settle_wager(&bank, wager_id, 20, 0.0, "Losing").await.unwrap();
+    assert_eq!(status, PayoutStatus::Delivered);
+    assert_eq!(balance(&bank, 20).await, 90.0);
+    assert_eq!(balance(&bank, HOUSE_ID).await, 110.0);
+}
+
+#[tokio::test]
+async fn stakes_the_player_cant_cover_move_nothing() {
+    let _database = scratch_database().await;
+    let bank = InMemoryBank::new(STARTING_BALANCE);
+
+    assert!(place_wager(&bank, 30, "test", 500.0, "Playing", HOUSE_ID).await.is_err());
+    assert_eq!(balance(&bank, 30).await, STARTING_BALANCE);
+    assert_eq!(balance(&bank, HOUSE_ID).await, STARTING_BALANCE);
+}
+
+#[tokio::test]
+async fn payouts_the_house_cant_make_yet_are_queued() {
+    let _database = scratch_database().await;
+    let bank = InMemoryBank::new(STARTING_BALANCE);
+
+    let wager_id = place_wager(&bank, 40, "test", 10.0, "Playing", HOUSE_ID).await.unwrap();
+    let status = settle_wager(&bank, wager_id, 40, 1000.0, "Winning").await.unwrap();
+    assert_eq!(status, PayoutStatus::Queued);
+    assert_eq!(balance(&bank, 40).await, 90.0);
+}
+
+#[tokio::test]
+async fn reconcile_refunds_unfinished_games_once() {
+    let _database = scratch_database().await;
+    let bank = InMemoryBank::new(STARTING_BALANCE);
+
+    place_wager(&bank, 50, "test", 10.0, "Playing", HOUSE_ID).await.unwrap();
+    let settled = place_wager(&bank, 51, "test", 10.0, "Playing", HOUSE_ID).await.unwrap();
+    settle_wager(&bank, settled, 51, 0.0, "Losing").await.unwrap();
+
+    reconcile(&bank).await.unwrap();
+    assert_eq!(balance(&bank, 50).await, STARTING_BALANCE);
+    assert_eq!(balance(&bank, 51).await, 90.0);
+
+    reconcile(&bank).await.unwrap();
+    assert_eq!(balance(&bank, 50).await, STARTING_BALANCE);
+    assert_eq!(balance(&bank, HOUSE_ID).await, 110.0);
+}